@@ -0,0 +1,67 @@
+//! Shared-token reads from several threads at once: every thread traverses the same list
+//! through a `&GhostToken` or a `&TCellOwner`, for the ghost_cell and the TCell list.
+//!
+//! Run with `cargo bench --bench concurrent_read`.
+
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use cells_demo::{
+    ghost_cell::{init_list, Node},
+    tcell::TDeque,
+};
+use ghost_cell::GhostToken;
+use qcell::TCellOwner;
+
+const LEN: i32 = 100_000;
+const ROUNDS: usize = 20;
+const THREADS: [usize; 4] = [1, 2, 4, 8];
+
+/// Run `traverse` `ROUNDS` times on each of `threads` threads at once, checking every sum.
+fn read_concurrently(threads: usize, traverse: impl Fn() -> i64 + Sync) -> Duration {
+    let expected = i64::from(LEN) * i64::from(LEN - 1) / 2;
+    let traverse = &traverse;
+    let start = Instant::now();
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(move || {
+                for _ in 0..ROUNDS {
+                    assert_eq!(traverse(), expected);
+                }
+            });
+        }
+    });
+    start.elapsed()
+}
+
+fn report(name: &str, threads: usize, elapsed: Duration) {
+    let nodes = (threads * ROUNDS) as f64 * f64::from(LEN);
+    println!(
+        "{name}: {threads} threads x {ROUNDS} traversals of {LEN} nodes in {elapsed:?} ({:.0} Mnodes/s)",
+        nodes / elapsed.as_secs_f64() / 1e6
+    );
+}
+
+fn main() {
+    GhostToken::new(|mut token| {
+        let (head, _tail) = init_list(&mut token, LEN);
+        for threads in THREADS {
+            let elapsed = read_concurrently(threads, || {
+                Node::iter(&head, &token).map(|&x| i64::from(x)).sum()
+            });
+            report("ghost_cell", threads, elapsed);
+        }
+    });
+
+    struct Brand;
+    let mut deque = TDeque::new(TCellOwner::<Brand>::new());
+    for x in 0..LEN {
+        deque.push_back(x);
+    }
+    for threads in THREADS {
+        let elapsed = read_concurrently(threads, || deque.iter().map(|&x| i64::from(x)).sum());
+        report("tcell", threads, elapsed);
+    }
+}
@@ -0,0 +1,48 @@
+//! Building a 50k-element cell_family `Deque` with `collect` versus repeated insertion.
+//!
+//! Run with `cargo bench --bench deque_from_iter`.
+
+use std::time::{Duration, Instant};
+
+use cells_demo::cell_family::Deque;
+
+const LEN: usize = 50_000;
+
+/// The fastest of `rounds` runs of `build`, not counting the drop of the deque it returns.
+/// Only one deque can be alive per thread, so each one is dropped before the next is built.
+fn fastest(rounds: u32, build: impl Fn() -> Deque<usize>) -> Duration {
+    (0..rounds)
+        .map(|_| {
+            let start = Instant::now();
+            let deque = build();
+            let elapsed = start.elapsed();
+            assert_eq!(deque.iter().count(), LEN);
+            assert_eq!(deque.back(), Some(&(LEN - 1)));
+            elapsed
+        })
+        .min()
+        .unwrap()
+}
+
+fn main() {
+    let collect = fastest(20, || (0..LEN).collect());
+    let add_last = fastest(20, || {
+        let mut deque = Deque::new();
+        for x in 0..LEN {
+            deque.add_last(x);
+        }
+        deque
+    });
+    // walks the whole deque to find its end before every insertion, so a single run will do
+    let walk_to_end = fastest(1, || {
+        let mut deque = Deque::new();
+        for x in 0..LEN {
+            deque.insert(x, x);
+        }
+        deque
+    });
+
+    println!(
+        "{LEN} elements: collect {collect:?}, add_last {add_last:?}, walk to the end {walk_to_end:?}"
+    );
+}
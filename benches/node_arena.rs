@@ -0,0 +1,39 @@
+//! Construction of a 100k-node ghost_cell list in a `NodeArena` versus one `Arc` per node.
+//!
+//! Run with `cargo bench --bench node_arena`.
+
+use std::time::{Duration, Instant};
+
+use cells_demo::ghost_cell::{init_list, Node, NodeArena};
+use ghost_cell::GhostToken;
+
+const LEN: i32 = 100_000;
+const ROUNDS: u32 = 20;
+
+/// The fastest of `ROUNDS` runs of `build`, which shouldn't include the teardown.
+fn fastest(mut build: impl FnMut() -> Duration) -> Duration {
+    (0..ROUNDS).map(|_| build()).min().unwrap()
+}
+
+fn main() {
+    GhostToken::new(|mut token| {
+        let arena = fastest(|| {
+            let start = Instant::now();
+            let mut arena = NodeArena::with_capacity(LEN as usize);
+            let head = arena.from_iter(&mut token, 0..LEN).unwrap();
+            let elapsed = start.elapsed();
+            assert_eq!(arena.view_as_vec(head, &token).len(), LEN as usize);
+            elapsed
+        });
+
+        let arc = fastest(|| {
+            let start = Instant::now();
+            let (head, _tail) = init_list(&mut token, LEN);
+            let elapsed = start.elapsed();
+            assert_eq!(Node::count_from(&head, &token), LEN as usize);
+            elapsed
+        });
+
+        println!("{LEN} nodes: arena {arena:?}, Arc {arc:?}");
+    });
+}
@@ -0,0 +1,267 @@
+use std::{cell::Cell, rc::Rc};
+
+use cells_demo::{
+    cell_family::{Deque, FooCellOwner},
+    demo_support::DropCounter,
+};
+
+fn two_aliases_example() {
+    #[derive(Debug)]
+    struct MyStruct {
+        data: usize,
+    }
+    // cells usage
+    cell_family::define!(type XFamily: XCellOwner for XCell<T>);
+    let mut owner = XCellOwner::new();
+    let cell = XCell::new(MyStruct { data: 123 });
+    let ref1 = Rc::new(cell);
+    let ref2 = ref1.clone();
+    {
+        // mutation through ref1
+        ref1.get_mut(&mut owner).data = 35;
+        assert_eq!(ref1.get(&owner).data, 35);
+        assert_eq!(ref2.get(&owner).data, 35);
+    }
+    {
+        // mutation through ref2
+        ref2.get_mut(&mut owner).data = 42;
+        assert_eq!(ref1.get(&owner).data, 42);
+        assert_eq!(ref2.get(&owner).data, 42);
+    }
+}
+
+fn deque_example() {
+    // Caution: given deque can only have one instance because its type marker is fixed
+    // This is bad, deque should be parametrized by the type marker, see qcell and tcell examples
+    let mut deque = Deque::<usize>::new();
+    deque.add_first(2);
+    deque.add_first(1);
+    deque.add_last(3);
+    println!("{:?}", deque);
+
+    assert_eq!(deque.front(), Option::Some(&1));
+    assert_eq!(deque.back(), Option::Some(&3));
+
+    let mut sum = 0;
+    for x in &deque {
+        sum += x;
+    }
+    assert_eq!(sum, 6);
+
+    let owned = deque.to_vec();
+    assert_eq!(owned, [1, 2, 3]);
+    assert_eq!(deque.as_vec(), owned.iter().collect::<Vec<_>>());
+}
+
+fn deque_ends_example() {
+    let mut deque = Deque::<usize>::new();
+    assert_eq!(deque.front(), Option::None);
+    assert_eq!(deque.back(), Option::None);
+
+    deque.add_last(2);
+    deque.add_first(1);
+    deque.add_last(3);
+    assert_eq!(
+        (deque.front(), deque.back()),
+        (Option::Some(&1), Option::Some(&3))
+    );
+
+    assert_eq!(deque.pop_first(), Option::Some(1));
+    assert_eq!(
+        (deque.front(), deque.back()),
+        (Option::Some(&2), Option::Some(&3))
+    );
+    assert_eq!(deque.pop_last(), Option::Some(3));
+    assert_eq!(
+        (deque.front(), deque.back()),
+        (Option::Some(&2), Option::Some(&2))
+    );
+    assert_eq!(deque.pop_last(), Option::Some(2));
+    assert_eq!((deque.front(), deque.back()), (Option::None, Option::None));
+    assert_eq!(deque.pop_first(), Option::None);
+}
+
+/// `front` and `back` read through `head` and `tail` without walking the deque.
+#[cfg(debug_assertions)]
+fn deque_ends_visits_example() {
+    let mut deque = Deque::from(vec![2, 3]);
+    deque.add_first(1);
+    deque.add_last(4);
+    assert_eq!(deque.pop_last(), Option::Some(4));
+    deque.add_first(0);
+    assert_eq!(deque.pop_first(), Option::Some(0));
+
+    let visits = deque.node_visits();
+    assert_eq!(
+        (deque.front(), deque.back()),
+        (Option::Some(&1), Option::Some(&3))
+    );
+    assert_eq!(deque.node_visits(), visits);
+
+    // a traversal on the other hand visits every node once
+    let len = deque.iter().count();
+    assert_eq!(len, 3);
+    assert_eq!(deque.node_visits(), visits + len);
+}
+
+fn rotation_example() {
+    let mut deque = Deque::<usize>::new();
+    deque.rotate_left(3);
+    for x in 1..=5 {
+        deque.add_last(x);
+    }
+
+    deque.rotate_left(2);
+    assert_eq!(deque.to_vec(), [3, 4, 5, 1, 2]);
+    deque.rotate_right(1);
+    assert_eq!(deque.to_vec(), [2, 3, 4, 5, 1]);
+    deque.rotate_right(6);
+    assert_eq!(deque.to_vec(), [1, 2, 3, 4, 5]);
+    assert_eq!(
+        (deque.front(), deque.back()),
+        (Option::Some(&1), Option::Some(&5))
+    );
+}
+
+fn from_vec_example() {
+    let deque = Deque::from(vec![1, 2, 3]);
+    assert_eq!(deque.as_vec(), [&1, &2, &3]);
+}
+
+fn get_mut_example() {
+    let mut deque = Deque::from(vec![1, 2, 3]);
+    *deque.get_mut(0).unwrap() = 10;
+    *deque.get_mut(1).unwrap() += 10;
+    assert_eq!(deque.get_mut(3), Option::None);
+    assert_eq!(deque.to_vec(), [10, 12, 3]);
+    assert_eq!(deque.front(), Option::Some(&10));
+}
+
+fn truncate_example() {
+    let mut deque = Deque::from(vec![1, 2, 3, 4]);
+    deque.truncate(2);
+    assert_eq!(deque.to_vec(), [1, 2]);
+    assert_eq!(deque.back(), Option::Some(&2));
+    deque.truncate(5);
+    assert_eq!(deque.to_vec(), [1, 2]);
+    deque.clear();
+    assert_eq!(deque.to_vec(), []);
+    assert_eq!((deque.front(), deque.back()), (Option::None, Option::None));
+    drop(deque);
+
+    let drops = Rc::new(Cell::new(0));
+    let mut deque = Deque::new();
+    for _ in 0..4 {
+        deque.add_last(DropCounter::new(&drops));
+    }
+    deque.truncate(1);
+    assert_eq!(drops.get(), 3);
+    deque.clear();
+    assert_eq!(drops.get(), 4);
+
+    deque.add_last(DropCounter::new(&drops));
+    deque.add_last(DropCounter::new(&drops));
+    // dropping the deque unlinks its nodes, so their strong `previous` links don't leak them
+    drop(deque);
+    assert_eq!(drops.get(), 6);
+}
+
+fn insert_example() {
+    let mut deque = Deque::from(vec![2, 4]);
+    deque.insert(0, 1);
+    deque.insert(2, 3);
+    deque.insert(4, 5);
+    assert_eq!(deque.to_vec(), [1, 2, 3, 4, 5]);
+    assert_eq!(
+        (deque.front(), deque.back()),
+        (Option::Some(&1), Option::Some(&5))
+    );
+    deque.rotate_right(1);
+    assert_eq!(deque.to_vec(), [5, 1, 2, 3, 4]);
+}
+
+fn remove_example() {
+    let mut deque = Deque::from(vec![1, 2, 3, 4, 5]);
+    assert_eq!(deque.remove(5), Option::None);
+    assert_eq!(deque.remove(0), Option::Some(1));
+    assert_eq!(deque.remove(1), Option::Some(3));
+    assert_eq!(deque.remove(2), Option::Some(5));
+    assert_eq!(deque.to_vec(), [2, 4]);
+    assert_eq!(
+        (deque.front(), deque.back()),
+        (Option::Some(&2), Option::Some(&4))
+    );
+    deque.rotate_right(1);
+    assert_eq!(deque.to_vec(), [4, 2]);
+}
+
+fn drain_example() {
+    let mut deque = Deque::from(vec![1, 2, 3]);
+    let drained: Vec<_> = deque.drain().collect();
+    assert_eq!(drained, [1, 2, 3]);
+    assert_eq!(deque.to_vec(), []);
+
+    for x in 1..=3 {
+        deque.add_last(x);
+    }
+    assert_eq!(deque.drain().next(), Option::Some(1));
+    assert_eq!((deque.front(), deque.back()), (Option::None, Option::None));
+}
+
+fn append_example() {
+    let mut deque = Deque::from(vec![1, 2, 3]);
+    // a second deque can't be created while this one lives, so the elements come in a `Vec`
+    assert!(FooCellOwner::try_new().is_none());
+    let mut other = vec![4, 5];
+    deque.append(&mut other);
+    assert_eq!(deque.to_vec(), [1, 2, 3, 4, 5]);
+    assert_eq!(deque.back(), Option::Some(&5));
+    assert!(other.is_empty());
+
+    deque.append(&mut other);
+    assert_eq!(deque.to_vec(), [1, 2, 3, 4, 5]);
+}
+
+fn debug_structure_example() {
+    let deque = Deque::from(vec![1, 2, 3]);
+    let structure = deque.debug_structure();
+    println!("{}", structure);
+
+    let lines: Vec<_> = structure.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].contains(": 1 (previous: None, next: 0x"));
+    assert!(lines[2].ends_with("next: None)"));
+    // the `next` of each node is the address the following line starts with
+    let address = |line: &str| line.split(':').next().unwrap().to_string();
+    assert!(lines[0].ends_with(&format!("next: {})", address(lines[1]))));
+    assert!(lines[2].contains(&format!("previous: {},", address(lines[1]))));
+}
+
+fn from_iter_example() {
+    let deque: Deque<_> = (1..=5).filter(|x| x % 2 == 1).collect();
+    assert_eq!(deque.to_vec(), [1, 3, 5]);
+    assert_eq!(deque.back(), Option::Some(&5));
+    drop(deque);
+
+    let deque: Deque<usize> = (0..100).collect();
+    assert_eq!(deque.iter().count(), 100);
+    assert_eq!(deque.back(), Option::Some(&99));
+}
+
+fn main() {
+    deque_example();
+    deque_ends_example();
+    #[cfg(debug_assertions)]
+    deque_ends_visits_example();
+    rotation_example();
+    from_vec_example();
+    two_aliases_example();
+    get_mut_example();
+    truncate_example();
+    insert_example();
+    remove_example();
+    drain_example();
+    append_example();
+    debug_structure_example();
+    from_iter_example();
+}
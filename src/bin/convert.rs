@@ -0,0 +1,42 @@
+use ::ghost_cell::GhostToken;
+use ::qcell::{QCellOwner, TCellOwner};
+use cells_demo::{
+    convert::{ghost_to_tcell, qcell_to_deque},
+    ghost_cell, qcell, tcell,
+};
+
+fn ghost_to_tcell_example() {
+    struct Brand;
+    let mut tcell_token = TCellOwner::<Brand>::new();
+    GhostToken::new(|mut ghost_token| {
+        let (head, _tail) = ghost_cell::init_list(&mut ghost_token, 3);
+
+        let converted = ghost_to_tcell(&head, &ghost_token, &mut tcell_token);
+        assert_eq!(
+            tcell::Node::view_as_vec(converted.as_ref(), &tcell_token),
+            ghost_cell::Node::view_as_vec(&head, &ghost_token)
+        );
+        println!(
+            "{:?}",
+            tcell::Node::view_as_vec(converted.as_ref(), &tcell_token)
+        );
+    });
+}
+
+fn qcell_to_deque_example() {
+    let mut owner = QCellOwner::new();
+    let list = qcell::Node::from_iter(&mut owner, [3, 1, 2]);
+
+    let deque = qcell_to_deque(list.as_ref(), &owner);
+    assert_eq!(deque.to_vec(), [3, 1, 2]);
+    assert_eq!(
+        deque.iter().collect::<Vec<_>>(),
+        qcell::Node::view_as_vec(list.as_ref(), &owner)
+    );
+    println!("{:?}", deque);
+}
+
+fn main() {
+    ghost_to_tcell_example();
+    qcell_to_deque_example();
+}
@@ -0,0 +1,1411 @@
+use cells_demo::ghost_cell::{Node, NodePtr};
+use ghost_cell::GhostToken;
+
+#[allow(dead_code)]
+fn print_list<'id, T: std::fmt::Debug>(list: &NodePtr<'id, T>, token: &GhostToken<'id>) {
+    println!(
+        "{}",
+        Node::iter(list, token)
+            .map(|n| format!("{:?}", n))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+}
+
+mod ownership {
+    pub mod data_structure_lib {
+        use std::sync::Arc;
+
+        use ghost_cell::{GhostCell, GhostToken};
+
+        // private struct, shouldn't be exposed to users
+        #[allow(dead_code)]
+        struct Rep {
+            a: i32,
+        }
+        type RepPointer<'id> = Arc<GhostCell<'id, Rep>>;
+
+        pub struct S1<'id> {
+            data: RepPointer<'id>,
+        }
+
+        impl<'id> S1<'id> {
+            pub fn new(a: i32) -> Self {
+                Self {
+                    data: Arc::new(GhostCell::new(Rep { a })),
+                }
+            }
+
+            /// mixing self' and other's representations is allowed when they
+            /// have common brand
+            pub fn mix_representations(&mut self, other: &S1<'id>) {
+                let other_rep = Arc::clone(&other.data);
+                self.data = other_rep;
+            }
+        }
+
+        #[allow(dead_code)]
+        pub struct SWithToken<'id> {
+            token: GhostToken<'id>,
+            data: RepPointer<'id>,
+        }
+
+        impl<'id> SWithToken<'id> {
+            pub fn new(a: i32, token: GhostToken<'id>) -> Self {
+                Self {
+                    token,
+                    data: Arc::new(GhostCell::new(Rep { a })),
+                }
+            }
+
+            // Does not compile, lifetimes don't match
+            pub fn mix_representations_fails<'id2>(&mut self, _other: &SWithToken<'id2>) {
+                // let other_rep = Arc::clone(&other.data);
+                // self.data = other_rep;
+            }
+        }
+    }
+    pub mod client_lib {
+        use ghost_cell::GhostToken;
+
+        use cells_demo::ghost_cell::{init_list, ListWrapper};
+
+        use super::data_structure_lib::*;
+
+        pub fn mix_representations() {
+            let mut s1_1 = S1::new(1);
+            let s1_2 = S1::new(2);
+
+            s1_1.mix_representations(&s1_2);
+        }
+
+        pub fn try_put_two_structs_in_one_vector() {
+            GhostToken::new(|token1| {
+                GhostToken::new(|token2| {
+                    let swt1 = SWithToken::new(1, token1);
+                    let swt2 = SWithToken::new(2, token2);
+
+                    let _swts1 = [swt1];
+                    let _swts2 = [swt2];
+
+                    // does not compile as swt lists have different types (lifetimes in particular):
+                    // swts1.append(&mut swts2);
+                })
+            })
+        }
+
+        pub fn mix_representations_fails() {
+            GhostToken::new(|token1| {
+                GhostToken::new(|token2| {
+                    let mut swt1 = SWithToken::new(1, token1);
+                    let swt2 = SWithToken::new(2, token2);
+
+                    swt1.mix_representations_fails(&swt2);
+                })
+            })
+        }
+
+        pub fn immutable_incoming_aliases_allowed() {
+            GhostToken::new(|mut token| {
+                let (list, _tail) = init_list(&mut token, 5);
+                let list_wrapper = ListWrapper::new(list, token);
+
+                let token_alias = list_wrapper.expose_token();
+                let node_alias = list_wrapper.expose_node().unwrap();
+                let _x = node_alias.borrow(token_alias).data;
+            });
+        }
+
+        pub fn mutable_incoming_alias_allowed() {
+            GhostToken::new(|mut token| {
+                let (list, _tail) = init_list(&mut token, 5);
+                let mut list_wrapper = ListWrapper::new(list, token);
+
+                let mut_node_ref = list_wrapper.expose_mut_node().unwrap();
+                mut_node_ref.data = 666;
+                println!("{:?}", list_wrapper);
+            });
+        }
+
+        pub fn run_all_examples() {
+            mix_representations();
+            mix_representations_fails();
+            try_put_two_structs_in_one_vector();
+            immutable_incoming_aliases_allowed();
+            mutable_incoming_alias_allowed();
+        }
+    }
+}
+
+mod dllist_client_lib {
+    use std::{
+        cell::Cell,
+        cmp::Ordering,
+        collections::{hash_map::DefaultHasher, HashSet, VecDeque},
+        hash::{Hash, Hasher},
+        ops::ControlFlow,
+        rc::Rc,
+        sync::Arc,
+    };
+
+    use ghost_cell::GhostToken;
+
+    use cells_demo::{
+        demo_support::DropCounter,
+        ghost_cell::{
+            init_list, with_cyclic_list, EmptyListError, ListWrapper, LruList, Node, NodeArena,
+            NodePtr,
+        },
+    };
+
+    pub fn list_wrapper_usage() {
+        // ListWrapper can store the token that owns its list nodes
+        // This allows not passing token everywhere
+        GhostToken::new(|token| {
+            let list = ListWrapper::create(token, [1, 2, 3, 4]);
+            println!("{:?}", list);
+
+            for n in list.iter() {
+                println!("{}", n);
+            }
+        });
+    }
+
+    pub fn view_as_vec() {
+        GhostToken::new(|mut token| {
+            let (list, _tail) = init_list(&mut token, 5);
+
+            let view = Node::view_as_vec(&list, &token);
+            println!("{:?}", view);
+        });
+    }
+
+    pub fn iter_with_index() {
+        GhostToken::new(|mut token| {
+            let (list, _tail) = init_list(&mut token, 3);
+
+            let indexed = Node::iter_with_index(&list, &token).collect::<Vec<_>>();
+            assert_eq!(indexed, [(0, &0), (1, &1), (2, &2)]);
+            println!("{:?}", indexed);
+        });
+    }
+
+    pub fn max_and_min_by_key() {
+        struct Item {
+            name: &'static str,
+            weight: u32,
+        }
+
+        GhostToken::new(|mut token| {
+            let head = Node::new(Item {
+                name: "a",
+                weight: 3,
+            });
+            let mut tail = Arc::clone(&head);
+            for (name, weight) in [("b", 7), ("c", 1), ("d", 7), ("e", 1)] {
+                let node = Node::new(Item { name, weight });
+                Node::insert_next(&tail, Arc::clone(&node), &mut token);
+                tail = node;
+            }
+
+            let heaviest = Node::max_by_key(&head, &token, |item| item.weight).unwrap();
+            let lightest = Node::min_by_key(&head, &token, |item| item.weight).unwrap();
+            assert_eq!(heaviest.borrow(&token).data.name, "b");
+            assert_eq!(lightest.borrow(&token).data.name, "c");
+
+            // the returned pointer can be used to mutate the found node
+            heaviest.borrow_mut(&mut token).data.weight = 0;
+            let lightest = Node::min_by_key(&head, &token, |item| item.weight).unwrap();
+            assert_eq!(lightest.borrow(&token).data.name, "b");
+        });
+    }
+
+    pub fn fused_iter() {
+        GhostToken::new(|mut token| {
+            let (list, _tail) = init_list(&mut token, 2);
+
+            let mut iter = Node::iter(&list, &token);
+            assert_eq!(iter.next(), Some(&0));
+            assert_eq!(iter.next(), Some(&1));
+            for _ in 0..3 {
+                assert_eq!(iter.next(), None);
+            }
+        });
+    }
+
+    pub fn for_each_pair() {
+        GhostToken::new(|token| {
+            let list = ListWrapper::create(token, [1, 2, 3]);
+
+            let mut pairs = vec![];
+            Node::for_each_pair(&list.expose_node().unwrap(), list.expose_token(), |a, b| {
+                pairs.push((*a, *b))
+            });
+            assert_eq!(pairs, [(1, 2), (2, 3)]);
+        });
+        GhostToken::new(|token| {
+            let list = ListWrapper::create(token, [1]);
+
+            let mut calls = 0;
+            Node::for_each_pair(&list.expose_node().unwrap(), list.expose_token(), |_, _| {
+                calls += 1
+            });
+            assert_eq!(calls, 0);
+        });
+    }
+
+    pub fn binary_search() {
+        GhostToken::new(|token| {
+            let list = ListWrapper::create(token, [1, 3, 5]);
+            let (head, token) = (list.expose_node().unwrap(), list.expose_token());
+
+            assert_eq!(Node::binary_search(&head, token, &3), Ok(1));
+            assert_eq!(Node::binary_search(&head, token, &4), Err(2));
+            assert_eq!(Node::binary_search(&head, token, &9), Err(3));
+        });
+    }
+
+    pub fn partition_point() {
+        GhostToken::new(|token| {
+            let list = ListWrapper::create(token, [1, 2, 3, 4, 5]);
+            let (head, token) = (list.expose_node().unwrap(), list.expose_token());
+
+            assert_eq!(Node::partition_point(&head, token, |x| *x < 3), 2);
+            assert_eq!(Node::partition_point(&head, token, |_| false), 0);
+            assert_eq!(Node::partition_point(&head, token, |_| true), 5);
+        });
+    }
+
+    pub fn chunk_view() {
+        GhostToken::new(|token| {
+            let list = ListWrapper::create(token, [1, 2, 3, 4, 5]);
+            let (head, token) = (list.expose_node().unwrap(), list.expose_token());
+
+            let chunks = Node::chunk_view(&head, token, 2);
+            assert_eq!(chunks, [vec![&1, &2], vec![&3, &4], vec![&5]]);
+            assert!(Node::chunk_view(&head, token, 0).is_empty());
+        });
+    }
+
+    pub fn windows_view() {
+        GhostToken::new(|token| {
+            let list = ListWrapper::create(token, [1, 2, 3]);
+            let (head, token) = (list.expose_node().unwrap(), list.expose_token());
+
+            let windows = Node::windows_view(&head, token, 2);
+            assert_eq!(windows, [vec![&1, &2], vec![&2, &3]]);
+            assert!(Node::windows_view(&head, token, 4).is_empty());
+        });
+    }
+
+    pub fn self_insertion() {
+        GhostToken::new(|mut token| {
+            let (list, _tail) = init_list(&mut token, 3);
+            let middle = Arc::clone(list.borrow(&token).next().unwrap());
+            assert!(Node::same(&middle, list.borrow(&token).next().unwrap()));
+            assert!(!Node::same(&middle, &list));
+
+            Node::insert_next(&middle, Arc::clone(&middle), &mut token);
+            assert_eq!(Node::view_as_vec(&list, &token), [&0, &1, &2]);
+        });
+    }
+
+    pub fn reverse_keeps_nodes() {
+        GhostToken::new(|mut token| {
+            let (list, _tail) = init_list(&mut token, 4);
+            let before = Node::as_ptr_vec(&list, &token);
+
+            let reversed = Node::reverse(&list, &mut token);
+            let mut after = Node::as_ptr_vec(&reversed, &token);
+            assert_eq!(Node::view_as_vec(&reversed, &token), [&3, &2, &1, &0]);
+
+            after.reverse();
+            assert_eq!(before, after);
+        });
+    }
+
+    pub fn empty_list_wrapper() {
+        GhostToken::new(|token| {
+            let mut list = ListWrapper::<i32>::empty(token);
+            assert_eq!(list.len(), 0);
+            assert!(list.is_empty());
+            assert_eq!(list.iter().next(), None);
+            assert_eq!(format!("{:?}", list), "[]");
+
+            list.push_back(1);
+            list.push_back(2);
+            assert_eq!(list.len(), 2);
+            assert_eq!(format!("{:?}", list), "[1, 2]");
+        });
+    }
+
+    pub fn remove_value() {
+        GhostToken::new(|mut token| {
+            let (list, _tail) = init_list(&mut token, 3);
+            let middle = Arc::clone(list.borrow(&token).next().unwrap());
+
+            assert_eq!(Node::remove_value(middle, &mut token), Some(1));
+            assert_eq!(Node::view_as_vec(&list, &token), [&0, &2]);
+        });
+        GhostToken::new(|mut token| {
+            let (list, tail) = init_list(&mut token, 3);
+
+            // `tail` is still alive, so the data can't be moved out
+            assert_eq!(Node::remove_value(Arc::clone(&tail), &mut token), None);
+            assert_eq!(Node::view_as_vec(&list, &token), [&0, &1]);
+            assert_eq!(tail.borrow(&token).data, 2);
+        });
+    }
+
+    pub fn rposition() {
+        GhostToken::new(|mut token| {
+            let head = Node::new(1);
+            let mut tail = Arc::clone(&head);
+            for value in [2, 1, 3] {
+                let node = Node::new(value);
+                Node::insert_next(&tail, Arc::clone(&node), &mut token);
+                tail = node;
+            }
+
+            for value in [1, 2, 3, 4] {
+                let forward = Node::iter(&head, &token)
+                    .enumerate()
+                    .filter(|(_, data)| **data == value)
+                    .map(|(i, _)| i)
+                    .last();
+                assert_eq!(Node::rposition(&tail, &token, &value), forward);
+            }
+            assert_eq!(Node::rposition(&tail, &token, &1), Some(2));
+        });
+    }
+
+    pub fn iter_rev() {
+        GhostToken::new(|token| {
+            let list = ListWrapper::create(token, [1, 2, 3]);
+            let (tail, token) = (list.expose_tail().unwrap(), list.expose_token());
+
+            let reversed = Node::iter_rev(&tail, token)
+                .map(|node| node.borrow(token).data)
+                .collect::<Vec<_>>();
+            assert_eq!(reversed, [3, 2, 1]);
+        });
+    }
+
+    pub fn nth_back() {
+        GhostToken::new(|mut token| {
+            let (_head, tail) = init_list(&mut token, 4);
+            let data = |n| Node::nth_back(&tail, n, &token).map(|node| node.borrow(&token).data);
+
+            assert_eq!(data(0), Some(3));
+            assert_eq!(data(1), Some(2));
+            assert_eq!(data(3), Some(0));
+            assert_eq!(data(4), None);
+        });
+    }
+
+    pub fn from_iter() {
+        GhostToken::new(|mut token| {
+            let list = Node::from_iter(&mut token, 1..=3).unwrap();
+            assert_eq!(Node::view_as_vec(&list, &token), [&1, &2, &3]);
+
+            let empty = Node::from_iter(&mut token, Vec::<i32>::new());
+            assert_eq!(empty.err(), Some(EmptyListError));
+            assert_eq!(
+                EmptyListError.to_string(),
+                "can't build a list from no elements"
+            );
+        });
+    }
+
+    pub fn new_chain() {
+        GhostToken::new(|mut token| {
+            let (head, tail) = Node::new_chain(&mut token, [1, 2, 3]).unwrap();
+            assert_eq!(head.borrow(&token).data, 1);
+            assert_eq!(tail.borrow(&token).data, 3);
+            assert!(head.borrow(&token).prev().is_none());
+            assert!(tail.borrow(&token).next().is_none());
+            assert_eq!(Node::distance(&head, &tail, &token), Some(2));
+            let backward = Node::iter_rev(&tail, &token)
+                .map(|node| node.borrow(&token).data)
+                .collect::<Vec<_>>();
+            assert_eq!(backward, [3, 2, 1]);
+
+            let (single_head, single_tail) = Node::new_chain(&mut token, [4]).unwrap();
+            assert!(Node::same(&single_head, &single_tail));
+            assert!(Node::new_chain(&mut token, Vec::<i32>::new()).is_none());
+        });
+    }
+
+    pub fn distance() {
+        GhostToken::new(|mut token| {
+            let (head, tail) = init_list(&mut token, 5);
+            let second = Arc::clone(head.borrow(&token).next().unwrap());
+
+            assert_eq!(Node::distance(&head, &second, &token), Some(1));
+            assert_eq!(Node::distance(&head, &tail, &token), Some(4));
+            assert_eq!(Node::distance(&second, &second, &token), Some(0));
+            assert_eq!(Node::distance(&tail, &head, &token), None);
+
+            assert_eq!(Node::count_from(&head, &token), 5);
+            assert_eq!(Node::count_from(&second, &token), 4);
+        });
+    }
+
+    pub fn detect_cycle() {
+        GhostToken::new(|mut token| {
+            let (head, tail) = init_list(&mut token, 5);
+            assert_eq!(Node::detect_cycle(&head, &token), None);
+            assert_eq!(Node::detect_cycle(&tail, &token), None);
+
+            // the tail points back at the second node
+            with_cyclic_list(&mut token, 5, 1, |head, tail, token| {
+                assert_eq!(Node::detect_cycle(head, token), Some(4));
+                assert_eq!(Node::detect_cycle(tail, token), Some(4));
+            });
+            // the tail points at itself
+            with_cyclic_list(&mut token, 5, 4, |head, _tail, token| {
+                assert_eq!(Node::detect_cycle(head, token), Some(1));
+            });
+        });
+    }
+
+    pub fn contains_node() {
+        GhostToken::new(|mut token| {
+            let (head, tail) = init_list(&mut token, 3);
+            let (other_head, _other_tail) = init_list(&mut token, 3);
+            let middle = Arc::clone(head.borrow(&token).next().unwrap());
+
+            assert!(Node::contains_node(&head, &head, &token));
+            assert!(Node::contains_node(&head, &middle, &token));
+            assert!(Node::contains_node(&head, &tail, &token));
+            // equal data, but a different node
+            assert!(!Node::contains_node(&head, &other_head, &token));
+            assert!(!Node::contains_node(&middle, &head, &token));
+        });
+    }
+
+    pub fn view_as_vec_limited() {
+        GhostToken::new(|mut token| {
+            let (head, _tail) = init_list(&mut token, 3);
+            assert_eq!(Node::view_as_vec_limited(&head, &token, 2), [&0, &1]);
+            assert_eq!(Node::view_as_vec_limited(&head, &token, 5), [&0, &1, &2]);
+
+            with_cyclic_list(&mut token, 3, 0, |head, _tail, token| {
+                assert_eq!(
+                    Node::view_as_vec_limited(head, token, 7),
+                    [&0, &1, &2, &0, &1, &2, &0]
+                );
+            });
+        });
+    }
+
+    pub fn fold_rev() {
+        GhostToken::new(|token| {
+            let list = ListWrapper::create(token, ["a", "b", "c"]);
+            let (tail, token) = (list.expose_tail().unwrap(), list.expose_token());
+
+            let nested = Node::fold_rev(&tail, token, String::from("nil"), |acc, x| {
+                format!("({} {})", x, acc)
+            });
+            assert_eq!(nested, "(a (b (c nil)))");
+        });
+    }
+
+    pub fn group_by() {
+        GhostToken::new(|token| {
+            let list = ListWrapper::create(token, [1, 1, 2, 3, 3, 3]);
+            let (head, token) = (list.expose_node().unwrap(), list.expose_token());
+
+            let groups = Node::group_by(&head, token, |x| *x);
+            assert_eq!(groups, [vec![&1, &1], vec![&2], vec![&3, &3, &3]]);
+        });
+    }
+
+    pub fn splice() {
+        GhostToken::new(|mut token| {
+            let (head, tail) = init_list(&mut token, 5);
+            let start = Arc::clone(head.borrow(&token).next().unwrap());
+            let end = Arc::clone(start.borrow(&token).next().unwrap());
+            let replacement = Node::new(7);
+            Node::insert_next(&replacement, Node::new(8), &mut token);
+
+            let new_start = Node::splice(&start, &end, Some(Arc::clone(&replacement)), &mut token);
+            assert!(Node::same(new_start.as_ref().unwrap(), &replacement));
+            assert_eq!(Node::view_as_vec(&head, &token), [&0, &7, &8, &3, &4]);
+            let backward = Node::fold_rev(&tail, &token, vec![], |mut acc, x| {
+                acc.push(*x);
+                acc
+            });
+            assert_eq!(backward, [4, 3, 8, 7, 0]);
+            // the removed range is detached
+            assert_eq!(Node::view_as_vec(&start, &token), [&1, &2]);
+        });
+        GhostToken::new(|mut token| {
+            let (head, tail) = init_list(&mut token, 5);
+            let start = Arc::clone(head.borrow(&token).next().unwrap());
+            let end = Arc::clone(start.borrow(&token).next().unwrap());
+
+            Node::splice(&start, &end, None, &mut token);
+            assert_eq!(Node::view_as_vec(&head, &token), [&0, &3, &4]);
+            assert_eq!(Node::rposition(&tail, &token, &0), Some(0));
+        });
+        GhostToken::new(|mut token| {
+            let (head, _tail) = init_list(&mut token, 3);
+
+            let new_head = Node::splice(&head, &head, None, &mut token).unwrap();
+            assert_eq!(Node::view_as_vec(&new_head, &token), [&1, &2]);
+        });
+    }
+
+    pub fn list_wrapper_len() {
+        GhostToken::new(|token| {
+            let mut list = ListWrapper::create(token, [1, 2, 3]);
+            assert_eq!(list.len(), 3);
+
+            list.push_back(4);
+            assert_eq!(list.pop_front(), Some(1));
+            assert_eq!(list.pop_back(), Some(4));
+            assert_eq!(list.len(), 2);
+            assert_eq!(format!("{:?}", list), "[2, 3]");
+
+            assert_eq!(list.pop_back(), Some(3));
+            assert_eq!(list.pop_back(), Some(2));
+            assert_eq!(list.pop_front(), None);
+            assert_eq!(list.len(), 0);
+
+            list.push_back(5);
+            list.push_back(6);
+            assert_eq!(list.len(), 2);
+            list.clear();
+            assert_eq!(list.len(), 0);
+            assert!(list.is_empty());
+        });
+    }
+
+    pub fn list_wrapper_for_each_mut() {
+        GhostToken::new(|token| {
+            let mut list = ListWrapper::create(token, [1, 2, 3]);
+            list.for_each_mut(|x| *x += 1);
+            assert_eq!(format!("{:?}", list), "[2, 3, 4]");
+        });
+    }
+
+    pub fn enumerate_mut() {
+        GhostToken::new(|mut token| {
+            let list = Node::from_iter(&mut token, [7; 4]).unwrap();
+            Node::enumerate_mut(&list, &mut token, |index, x| *x = index);
+            assert_eq!(Node::view_as_vec(&list, &token), [&0, &1, &2, &3]);
+        });
+    }
+
+    pub fn swap_adjacent() {
+        GhostToken::new(|mut token| {
+            let (first, tail) = init_list(&mut token, 4);
+            let second = Arc::clone(first.borrow(&token).next().unwrap());
+
+            assert!(Node::swap_adjacent(&first, &mut token));
+            let head = first.borrow(&token).prev().unwrap();
+            assert!(Node::same(&head, &second));
+            assert_eq!(Node::view_as_vec(&head, &token), [&1, &0, &2, &3]);
+
+            // `first` keeps its identity and moves on by another position
+            assert!(Node::swap_adjacent(&first, &mut token));
+            assert_eq!(Node::view_as_vec(&head, &token), [&1, &2, &0, &3]);
+
+            assert!(!Node::swap_adjacent(&tail, &mut token));
+            let backward = Node::iter_rev(&tail, &token)
+                .map(|node| node.borrow(&token).data)
+                .collect::<Vec<_>>();
+            assert_eq!(backward, [3, 0, 2, 1]);
+        });
+    }
+
+    pub fn list_wrapper_search() {
+        GhostToken::new(|token| {
+            let list = ListWrapper::create(token, [5, 6, 7]);
+            assert!(list.contains(&6));
+            assert!(!list.contains(&8));
+            assert_eq!(list.position(&7), Some(2));
+            assert_eq!(list.position(&8), None);
+        });
+        GhostToken::new(|token| {
+            let list = ListWrapper::<i32>::empty(token);
+            assert!(!list.contains(&1));
+            assert_eq!(list.position(&1), None);
+        });
+    }
+
+    pub fn list_wrapper_get() {
+        GhostToken::new(|token| {
+            let list = ListWrapper::create(token, [5, 6, 7]);
+            assert_eq!(list.get(0), Some(&5));
+            assert_eq!(list.get(2), Some(&7));
+            assert_eq!(list.get(3), None);
+        });
+    }
+
+    pub fn list_wrapper_remove_at() {
+        GhostToken::new(|token| {
+            let mut list = ListWrapper::create(token, [1, 2, 3, 4, 5]);
+            assert_eq!(list.remove_at(0), Some(1));
+            assert_eq!(list.remove_at(1), Some(3));
+            assert_eq!(list.remove_at(2), Some(5));
+            assert_eq!(list.remove_at(2), None);
+            assert_eq!(format!("{:?}", list), "[2, 4]");
+            assert_eq!(list.len(), 2);
+
+            // the tail is still correct after removing the old one
+            list.push_back(6);
+            assert_eq!(format!("{:?}", list), "[2, 4, 6]");
+        });
+    }
+
+    pub fn list_wrapper_split_off() {
+        GhostToken::new(|token| {
+            let mut list = ListWrapper::create(token, [1, 2, 3, 4, 5]);
+            assert_eq!(list.split_off(5), []);
+            assert_eq!(list.split_off(3), [4, 5]);
+            assert_eq!(format!("{:?}", list), "[1, 2, 3]");
+            assert_eq!(list.len(), 3);
+
+            list.push_back(6);
+            assert_eq!(list.split_off(1), [2, 3, 6]);
+            assert_eq!(list.split_off(0), [1]);
+            assert!(list.is_empty());
+            assert_eq!(list.len(), 0);
+        });
+    }
+
+    pub fn list_wrapper_retain() {
+        GhostToken::new(|token| {
+            let mut list = ListWrapper::create(token, 1..=6);
+            list.retain(|x| x % 2 == 0);
+            assert_eq!(format!("{:?}", list), "[2, 4, 6]");
+            assert_eq!(list.len(), 3);
+            list.push_back(8);
+            list.push_front(0);
+            assert_eq!(format!("{:?}", list), "[0, 2, 4, 6, 8]");
+
+            list.retain(|_| false);
+            assert!(list.is_empty());
+            assert_eq!(list.len(), 0);
+            list.push_back(1);
+            assert_eq!(format!("{:?}", list), "[1]");
+        });
+    }
+
+    pub fn list_wrapper_reverse() {
+        GhostToken::new(|token| {
+            let mut list = ListWrapper::create(token, [1, 2, 3]);
+            list.reverse();
+            assert_eq!(format!("{:?}", list), "[3, 2, 1]");
+            assert_eq!(list.iter().copied().collect::<Vec<_>>(), [3, 2, 1]);
+
+            list.push_back(0);
+            list.push_front(4);
+            assert_eq!(format!("{:?}", list), "[4, 3, 2, 1, 0]");
+            assert_eq!(list.pop_back(), Some(0));
+            assert_eq!(list.pop_back(), Some(1));
+        });
+        GhostToken::new(|token| {
+            let mut list = ListWrapper::<i32>::empty(token);
+            list.reverse();
+            assert!(list.is_empty());
+        });
+    }
+
+    pub fn list_wrapper_sort() {
+        GhostToken::new(|token| {
+            let mut list = ListWrapper::create(token, [3, 1, 4, 1, 5, 9, 2, 6]);
+            list.sort();
+            assert_eq!(
+                list.iter().copied().collect::<Vec<_>>(),
+                [1, 1, 2, 3, 4, 5, 6, 9]
+            );
+            assert_eq!(list.len(), 8);
+            assert_eq!(list.pop_back(), Some(9));
+            list.push_back(0);
+            assert_eq!(format!("{:?}", list), "[1, 1, 2, 3, 4, 5, 6, 0]");
+        });
+    }
+
+    pub fn list_wrapper_map_collect() {
+        GhostToken::new(|token| {
+            let list = ListWrapper::create(token, [1, 2, 3]);
+            let strings = list.map_collect(|x| format!("#{x}"));
+            assert_eq!(strings, ["#1", "#2", "#3"]);
+            assert_eq!(format!("{:?}", list), "[1, 2, 3]");
+        });
+    }
+
+    pub fn list_wrapper_insert_at() {
+        GhostToken::new(|token| {
+            let mut list = ListWrapper::create(token, [2, 4]);
+            list.insert_at(0, 1);
+            list.insert_at(2, 3);
+            list.insert_at(4, 5);
+            assert_eq!(format!("{:?}", list), "[1, 2, 3, 4, 5]");
+            assert_eq!(list.len(), 5);
+            assert_eq!(list.pop_back(), Some(5));
+            assert_eq!(list.pop_front(), Some(1));
+        });
+        GhostToken::new(|token| {
+            let mut list = ListWrapper::empty(token);
+            list.insert_at(0, 1);
+            assert_eq!(format!("{:?}", list), "[1]");
+        });
+    }
+
+    pub fn list_wrapper_hash() {
+        fn hash_of(list: &impl Hash) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            list.hash(&mut hasher);
+            hasher.finish()
+        }
+
+        /// A list of any brand, seen as its elements in order.
+        trait Elements<T> {
+            fn elements(&self) -> Vec<&T>;
+        }
+
+        impl<'id, T> Elements<T> for ListWrapper<'id, T> {
+            fn elements(&self) -> Vec<&T> {
+                self.iter().collect()
+            }
+        }
+
+        /// Lists of different brands are different types, so they can only share a
+        /// `HashSet` behind this brand-erasing newtype. Both `Eq` and `Hash` go through the
+        /// elements in order, so equal lists hash equally, as they do for `ListWrapper`.
+        struct AnyList<'a, T>(&'a dyn Elements<T>);
+
+        impl<T: PartialEq> PartialEq for AnyList<'_, T> {
+            fn eq(&self, other: &Self) -> bool {
+                self.0.elements() == other.0.elements()
+            }
+        }
+
+        impl<T: Eq> Eq for AnyList<'_, T> {}
+
+        impl<T: Hash> Hash for AnyList<'_, T> {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                self.0.elements().hash(state);
+            }
+        }
+
+        GhostToken::new(|token1| {
+            GhostToken::new(|token2| {
+                GhostToken::new(|token3| {
+                    let list1 = ListWrapper::create(token1, [1, 2, 3]);
+                    let list2 = ListWrapper::create(token2, [1, 2, 3]);
+                    let list3 = ListWrapper::create(token3, [3, 2, 1]);
+                    assert!(list1 == list2);
+                    assert!(list1 != list3);
+                    assert_eq!(hash_of(&list1), hash_of(&list2));
+                    // the newtype hashes like the list it wraps
+                    assert_eq!(hash_of(&AnyList(&list1)), hash_of(&list1));
+
+                    let lists: HashSet<AnyList<i32>> =
+                        [AnyList(&list1), AnyList(&list2), AnyList(&list3)].into();
+                    assert_eq!(lists.len(), 2);
+                    assert!(lists.contains(&AnyList(&list2)));
+                    assert!(lists.contains(&AnyList(&list3)));
+                })
+            })
+        });
+    }
+
+    pub fn list_wrapper_ord() {
+        GhostToken::new(|token1| {
+            GhostToken::new(|token2| {
+                let a = ListWrapper::create(token1, [1, 2]);
+                let b = ListWrapper::create(token2, [1, 3]);
+                assert!(a < b);
+                assert!(b >= a);
+            });
+        });
+        GhostToken::new(|token1| {
+            GhostToken::new(|token2| {
+                let shorter = ListWrapper::create(token1, [1]);
+                let longer = ListWrapper::create(token2, [1, 0]);
+                assert!(shorter < longer);
+                assert_eq!(longer.partial_cmp(&shorter), Some(Ordering::Greater));
+            });
+        });
+        GhostToken::new(|token1| {
+            GhostToken::new(|token2| {
+                let a = ListWrapper::create(token1, [1, 2]);
+                let b = ListWrapper::create(token2, [1, 2]);
+                assert_eq!(a.partial_cmp(&b), Some(Ordering::Equal));
+                assert!(a <= b);
+                assert!(a >= b);
+            });
+        });
+        GhostToken::new(|token| {
+            // `Ord` compares lists of the same brand only
+            let list = ListWrapper::create(token, [1, 2]);
+            assert_eq!(list.cmp(&list), Ordering::Equal);
+        });
+        GhostToken::new(|token1| {
+            GhostToken::new(|token2| {
+                let a = ListWrapper::create(token1, [1.0, f64::NAN]);
+                let b = ListWrapper::create(token2, [1.0, 2.0]);
+                assert_eq!(a.partial_cmp(&b), None);
+            });
+        });
+    }
+
+    pub fn is_sorted() {
+        GhostToken::new(|mut token| {
+            let (sorted, _tail) = init_list(&mut token, 4);
+            assert!(Node::is_sorted(&sorted, &token));
+
+            let unsorted = Node::reverse(&sorted, &mut token);
+            assert!(!Node::is_sorted(&unsorted, &token));
+
+            assert!(Node::is_sorted(&Node::new(1), &token));
+        });
+    }
+
+    pub fn merge_sorted() {
+        GhostToken::new(|mut token| {
+            let odd = Node::new(1);
+            let even = Node::new(2);
+            for (list, values) in [(&odd, [3, 5]), (&even, [4, 6])] {
+                let mut tail = Arc::clone(list);
+                for value in values {
+                    let node = Node::new(value);
+                    Node::insert_next(&tail, Arc::clone(&node), &mut token);
+                    tail = node;
+                }
+            }
+
+            let merged = Node::merge_sorted(Some(odd), Some(even), &mut token).unwrap();
+            assert_eq!(Node::view_as_vec(&merged, &token), [&1, &2, &3, &4, &5, &6]);
+
+            // the back-links were relinked as well
+            let mut tail = Arc::clone(&merged);
+            while let Some(next) = tail.borrow(&token).next().cloned() {
+                tail = next;
+            }
+            let backward = Node::iter_rev(&tail, &token)
+                .map(|node| node.borrow(&token).data)
+                .collect::<Vec<_>>();
+            assert_eq!(backward, [6, 5, 4, 3, 2, 1]);
+        });
+        GhostToken::new(|mut token| {
+            let (list, _tail) = init_list(&mut token, 2);
+
+            let merged = Node::merge_sorted(None, Some(list), &mut token).unwrap();
+            assert_eq!(Node::view_as_vec(&merged, &token), [&0, &1]);
+            assert!(Node::merge_sorted(None::<NodePtr<i32>>, None, &mut token).is_none());
+        });
+    }
+
+    pub fn merge_sort() {
+        for input in [
+            vec![3, 1, 4, 1, 5, 9, 2, 6],
+            vec![1, 2, 3, 4],
+            vec![4, 3, 2, 1],
+            vec![7],
+        ] {
+            GhostToken::new(|token| {
+                let (head, mut token) = ListWrapper::create(token, input.clone()).into_parts();
+                let head = head.unwrap();
+
+                let sorted = Node::merge_sort(&head, &mut token);
+                assert!(Node::is_sorted(&sorted, &token));
+                assert_eq!(Node::count_from(&sorted, &token), input.len());
+            });
+        }
+        GhostToken::new(|mut token| {
+            // equal keys keep their original order
+            let head = Node::new((2, 'a'));
+            Node::insert_next(&head, Node::new((1, 'b')), &mut token);
+            let third = Node::new((2, 'c'));
+            Node::insert_next(&head, Arc::clone(&third), &mut token);
+
+            let sorted = Node::merge_sort(&head, &mut token);
+            let keys = Node::iter(&sorted, &token)
+                .map(|(k, _)| *k)
+                .collect::<Vec<_>>();
+            assert_eq!(keys, [1, 2, 2]);
+            assert_eq!(Node::distance(&head, &third, &token), Some(1));
+        });
+    }
+
+    pub fn fold_while() {
+        GhostToken::new(|mut token| {
+            let (list, _tail) = init_list(&mut token, 10);
+
+            let mut visited = 0;
+            let sum = Node::fold_while(&list, &token, 0, |acc, x| {
+                visited += 1;
+                let acc = acc + x;
+                if acc > 5 {
+                    ControlFlow::Break(acc)
+                } else {
+                    ControlFlow::Continue(acc)
+                }
+            });
+            // 0 + 1 + 2 + 3 is the first sum above the threshold
+            assert_eq!(sum, 6);
+            assert_eq!(visited, 4);
+        });
+    }
+
+    pub fn any_and_all() {
+        GhostToken::new(|token| {
+            let list = ListWrapper::create(token, [2, 4, 5]);
+            let (head, token) = (list.expose_node().unwrap(), list.expose_token());
+
+            assert!(Node::all(&head, token, |x| *x > 0));
+            assert!(Node::any(&head, token, |x| *x > 0));
+            assert!(!Node::all(&head, token, |x| *x > 9));
+            assert!(!Node::any(&head, token, |x| *x > 9));
+            assert!(!Node::all(&head, token, |x| x % 2 == 0));
+            assert!(Node::any(&head, token, |x| x % 2 == 0));
+
+            let mut checked = 0;
+            Node::any(&head, token, |x| {
+                checked += 1;
+                *x == 2
+            });
+            assert_eq!(checked, 1);
+        });
+    }
+
+    pub fn dedup_by_key() {
+        struct Reading {
+            sensor: char,
+            value: i32,
+        }
+
+        GhostToken::new(|token| {
+            let readings = [('a', 1), ('a', 2), ('b', 3), ('b', 4), ('b', 5), ('a', 6)]
+                .map(|(sensor, value)| Reading { sensor, value });
+            let (head, mut token) = ListWrapper::create(token, readings).into_parts();
+            let head = head.unwrap();
+
+            Node::dedup_by_key(&head, &mut token, |reading| reading.sensor);
+            let kept = Node::iter(&head, &token)
+                .map(|reading| (reading.sensor, reading.value))
+                .collect::<Vec<_>>();
+            assert_eq!(kept, [('a', 1), ('b', 3), ('a', 6)]);
+        });
+    }
+
+    pub fn step_by_view() {
+        GhostToken::new(|mut token| {
+            let (list, _tail) = init_list(&mut token, 10);
+            assert_eq!(Node::step_by_view(&list, &token, 3), [&0, &3, &6, &9]);
+            assert_eq!(Node::step_by_view(&list, &token, 20), [&0]);
+        });
+    }
+
+    pub fn take_and_skip_while_view() {
+        GhostToken::new(|token| {
+            let (head, token) = ListWrapper::create(token, [1, 2, 3, 4, 1]).into_parts();
+            let head = head.unwrap();
+
+            assert_eq!(Node::take_while_view(&head, &token, |x| *x < 3), [&1, &2]);
+            assert_eq!(
+                Node::skip_while_view(&head, &token, |x| *x < 3),
+                [&3, &4, &1]
+            );
+            assert_eq!(Node::take_while_view(&head, &token, |_| true).len(), 5);
+            assert!(Node::skip_while_view(&head, &token, |_| true).is_empty());
+        });
+    }
+
+    pub fn flat_map() {
+        GhostToken::new(|token| {
+            let (head, mut token) = ListWrapper::create(token, [1, 2, 3]).into_parts();
+            let head = head.unwrap();
+
+            let expanded = Node::flat_map(&head, &mut token, |n| vec![*n; *n]).unwrap();
+            assert_eq!(
+                Node::view_as_vec(&expanded, &token),
+                [&1, &2, &2, &3, &3, &3]
+            );
+            assert!(Node::flat_map(&head, &mut token, |_| Vec::<i32>::new()).is_none());
+        });
+    }
+
+    pub fn scan() {
+        GhostToken::new(|token| {
+            let (head, mut token) = ListWrapper::create(token, [1, 2, 3, 4]).into_parts();
+
+            let prefix_sums = Node::scan(&head.unwrap(), &mut token, 0, |acc, x| acc + x).unwrap();
+            assert_eq!(Node::view_as_vec(&prefix_sums, &token), [&1, &3, &6, &10]);
+        });
+    }
+
+    pub fn rotate_to_front() {
+        GhostToken::new(|mut token| {
+            let (head, tail) = init_list(&mut token, 4);
+
+            let head = Node::rotate_to_front(&head, &2, &mut token).unwrap();
+            assert_eq!(Node::view_as_vec(&head, &token), [&2, &0, &1, &3]);
+            let head = Node::rotate_to_front(&head, &3, &mut token).unwrap();
+            assert_eq!(Node::view_as_vec(&head, &token), [&3, &2, &0, &1]);
+            assert!(Node::same(&head, &tail));
+            let head = Node::rotate_to_front(&head, &3, &mut token).unwrap();
+            assert_eq!(Node::view_as_vec(&head, &token), [&3, &2, &0, &1]);
+
+            assert!(Node::rotate_to_front(&head, &9, &mut token).is_none());
+            assert_eq!(Node::view_as_vec(&head, &token), [&3, &2, &0, &1]);
+        });
+    }
+
+    pub fn lru_list() {
+        GhostToken::new(|token| {
+            let mut lru = LruList::new(token, 3);
+            for (key, value) in [("a", 1), ("b", 2), ("c", 3)] {
+                assert_eq!(lru.put(key, value), None);
+            }
+            assert_eq!(lru.access(&"a"), Some(&1));
+            assert_eq!(lru.access(&"z"), None);
+
+            // "b" is now the least recently used entry
+            assert_eq!(lru.put("d", 4), Some(("b", 2)));
+            assert_eq!(lru.put("c", 30), None);
+            assert_eq!(lru.put("e", 5), Some(("a", 1)));
+
+            let keys = lru.iter().map(|(key, _)| *key).collect::<Vec<_>>();
+            assert_eq!(keys, ["e", "c", "d"]);
+            assert_eq!(lru.len(), 3);
+            assert_eq!(lru.access(&"c"), Some(&30));
+        });
+    }
+
+    pub fn has_shared_nodes() {
+        GhostToken::new(|mut token| {
+            let (head, tail) = Node::new_chain(&mut token, [1, 2, 3]).unwrap();
+            // the kept `tail` is an external reference
+            assert!(Node::has_shared_nodes(&head, &token));
+            drop(tail);
+            assert!(!Node::has_shared_nodes(&head, &token));
+
+            let head_alias = Arc::clone(&head);
+            assert!(Node::has_shared_nodes(&head, &token));
+            drop(head_alias);
+            assert_eq!(Node::into_vec(head, &mut token), [1, 2, 3]);
+        });
+    }
+
+    pub fn strong_counts() {
+        GhostToken::new(|mut token| {
+            let (head, tail) = init_list(&mut token, 3);
+            // `head` and `tail` are held here, the rest only by the list
+            assert_eq!(Node::strong_counts(&head, &token), [1, 1, 2]);
+
+            let middle = Arc::clone(head.borrow(&token).next().unwrap());
+            assert_eq!(Node::strong_counts(&head, &token), [1, 2, 2]);
+
+            assert_eq!(Node::remove_value(middle, &mut token), Some(1));
+            assert_eq!(Node::strong_counts(&head, &token), [1, 2]);
+            drop(tail);
+            assert_eq!(Node::strong_counts(&head, &token), [1, 1]);
+        });
+    }
+
+    pub fn into_vec() {
+        let drops = Rc::new(Cell::new(0));
+        GhostToken::new(|mut token| {
+            let (head, tail) =
+                Node::new_chain(&mut token, (0..4).map(|_| DropCounter::new(&drops))).unwrap();
+            let weak_head = Arc::downgrade(&head);
+            let weak_tail = Arc::downgrade(&tail);
+            drop(tail);
+
+            let data = Node::into_vec(head, &mut token);
+            assert_eq!(data.len(), 4);
+            // every node is gone, but the data was moved out rather than dropped
+            assert!(weak_head.upgrade().is_none());
+            assert!(weak_tail.upgrade().is_none());
+            assert_eq!(drops.get(), 0);
+            drop(data);
+            assert_eq!(drops.get(), 4);
+        });
+        GhostToken::new(|mut token| {
+            let (head, tail) = init_list(&mut token, 3);
+            assert_eq!(Node::into_vec(head, &mut token), [0, 1]);
+            assert_eq!(tail.borrow(&token).data, 2);
+            assert!(tail.borrow(&token).prev().is_none());
+        });
+    }
+
+    pub fn reduce_in_place() {
+        GhostToken::new(|mut token| {
+            let list = Node::from_iter(&mut token, [1, 2, 3, 4]).unwrap();
+            let sum = Node::reduce_in_place(list, &mut token, |acc, x| *acc += x);
+            assert_eq!(Node::view_as_vec(&sum, &token), [&10]);
+        });
+        let drops = Rc::new(Cell::new(0));
+        GhostToken::new(|mut token| {
+            let (head, _tail) =
+                Node::new_chain(&mut token, (0..4).map(|_| DropCounter::new(&drops))).unwrap();
+            let head = Node::reduce_in_place(head, &mut token, |_, x| drop(x));
+            assert_eq!(Node::count_from(&head, &token), 1);
+            // the kept `_tail` was unlinked, but its element wasn't folded
+            assert_eq!(drops.get(), 2);
+            drop(head);
+            assert_eq!(drops.get(), 3);
+        });
+        assert_eq!(drops.get(), 4);
+    }
+
+    pub fn lists_are_freed() {
+        let drops = Rc::new(Cell::new(0));
+        GhostToken::new(|token| {
+            let list = ListWrapper::create(token, (0..4).map(|_| DropCounter::new(&drops)));
+            assert_eq!(drops.get(), 0);
+            drop(list);
+            assert_eq!(drops.get(), 4);
+        });
+
+        let drops = Rc::new(Cell::new(0));
+        GhostToken::new(|mut token| {
+            let head = Node::new(DropCounter::new(&drops));
+            let mut tail = Arc::clone(&head);
+            for _ in 0..3 {
+                let node = Node::new(DropCounter::new(&drops));
+                Node::insert_next(&tail, Arc::clone(&node), &mut token);
+                tail = node;
+            }
+            // the kept `tail` pointer keeps only the last node alive
+            drop(head);
+            assert_eq!(drops.get(), 3);
+            drop(tail);
+            assert_eq!(drops.get(), 4);
+        });
+    }
+
+    pub fn to_vecdeque() {
+        GhostToken::new(|mut token| {
+            let (list, _tail) = init_list(&mut token, 4);
+
+            let deque = Node::to_vecdeque(&list, &token);
+            assert!(deque.iter().eq(Node::view_as_vec(&list, &token)));
+        });
+    }
+
+    pub fn from_vecdeque() {
+        GhostToken::new(|mut token| {
+            let deque = VecDeque::from([1, 2, 3]);
+
+            let list = Node::from_vecdeque(&mut token, deque.clone()).unwrap();
+            assert_eq!(Node::view_as_vec(&list, &token), [&1, &2, &3]);
+            assert_eq!(Node::to_vecdeque(&list, &token), deque);
+            assert!(Node::from_vecdeque(&mut token, VecDeque::<i32>::new()).is_none());
+        });
+    }
+
+    pub fn collect_into() {
+        GhostToken::new(|mut token| {
+            let (first, _tail) = init_list(&mut token, 2);
+            let (second, _tail) = init_list(&mut token, 3);
+
+            let mut buffer = Vec::with_capacity(5);
+            Node::collect_into(&first, &token, &mut buffer);
+            Node::collect_into(&second, &token, &mut buffer);
+            assert_eq!(buffer, [&0, &1, &0, &1, &2]);
+
+            buffer.clear();
+            Node::collect_into(&second, &token, &mut buffer);
+            assert_eq!(buffer, [&0, &1, &2]);
+        });
+    }
+
+    pub fn apply_at() {
+        GhostToken::new(|mut token| {
+            let (list, _tail) = init_list(&mut token, 3);
+
+            assert!(Node::apply_at(&list, 0, &mut token, |x| *x = 10));
+            assert!(Node::apply_at(&list, 1, &mut token, |x| *x += 10));
+            assert!(!Node::apply_at(&list, 3, &mut token, |x| *x = 99));
+            assert_eq!(Node::view_as_vec(&list, &token), [&10, &11, &2]);
+        });
+    }
+
+    pub fn replace_at() {
+        GhostToken::new(|mut token| {
+            let (list, _tail) = init_list(&mut token, 3);
+
+            assert_eq!(Node::replace_at(&list, 0, 10, &mut token), Some(0));
+            assert_eq!(Node::replace_at(&list, 1, 11, &mut token), Some(1));
+            assert_eq!(Node::replace_at(&list, 3, 13, &mut token), None);
+            assert_eq!(Node::view_as_vec(&list, &token), [&10, &11, &2]);
+        });
+    }
+
+    pub fn rotate_right() {
+        GhostToken::new(|token| {
+            let (head, mut token) = ListWrapper::create(token, [1, 2, 3, 4, 5]).into_parts();
+
+            let head = Node::rotate_right(&head.unwrap(), 2, &mut token);
+            assert_eq!(Node::view_as_vec(&head, &token), [&4, &5, &1, &2, &3]);
+            let tail = Node::node_at(&head, 4, &token).unwrap();
+            let backward = Node::fold_rev(&tail, &token, vec![], |mut acc, x| {
+                acc.push(*x);
+                acc
+            });
+            assert_eq!(backward, [3, 2, 1, 5, 4]);
+
+            let head = Node::rotate_right(&head, 5, &mut token);
+            assert_eq!(Node::view_as_vec(&head, &token), [&4, &5, &1, &2, &3]);
+            let head = Node::rotate_right(&head, 8, &mut token);
+            assert_eq!(Node::view_as_vec(&head, &token), [&1, &2, &3, &4, &5]);
+        });
+    }
+
+    pub fn node_arena() {
+        GhostToken::new(|mut token| {
+            let mut arena = NodeArena::new();
+            let arena_head = arena.from_iter(&mut token, 0..5).unwrap();
+            let (list, _tail) = init_list(&mut token, 5);
+            assert_eq!(
+                arena.view_as_vec(arena_head, &token),
+                Node::view_as_vec(&list, &token)
+            );
+
+            // move the second element to the back, then drop the third one
+            let arena_second = arena.next(arena_head, &token).unwrap();
+            let mut arena_tail = arena_second;
+            while let Some(next) = arena.next(arena_tail, &token) {
+                arena_tail = next;
+            }
+            arena.insert_next(arena_tail, arena_second, &mut token);
+            let third = arena
+                .next(arena.next(arena_head, &token).unwrap(), &token)
+                .unwrap();
+            arena.remove(third, &mut token);
+            *arena.get_mut(arena_head, &mut token) = 10;
+
+            let second = Arc::clone(list.borrow(&token).next().unwrap());
+            let mut tail = Arc::clone(&list);
+            while let Some(next) = tail.borrow(&token).next().cloned() {
+                tail = next;
+            }
+            Node::insert_next(&tail, second, &mut token);
+            let third = Arc::clone(
+                list.borrow(&token)
+                    .next()
+                    .unwrap()
+                    .borrow(&token)
+                    .next()
+                    .unwrap(),
+            );
+            Node::remove(&third, &mut token);
+            list.borrow_mut(&mut token).data = 10;
+
+            assert_eq!(
+                arena.view_as_vec(arena_head, &token),
+                Node::view_as_vec(&list, &token)
+            );
+            assert_eq!(arena.view_as_vec(arena_head, &token), [&10, &2, &4, &1]);
+            assert_eq!(arena.prev(arena_second, &token), Some(4));
+            assert_eq!(*arena.get(arena_second, &token), 1);
+        });
+    }
+
+    /// Dropping a 100k element list doesn't overflow the stack: the nodes are freed one
+    /// by one rather than recursively.
+    pub fn drop_long_list() {
+        GhostToken::new(|mut token| {
+            let (head, _tail) = init_list(&mut token, 100_000);
+            drop(head);
+        });
+    }
+
+    pub fn snapshot() {
+        let snapshot = GhostToken::new(|mut token| {
+            let (list, _tail) = init_list(&mut token, 4);
+            let snapshot = Node::snapshot(&list, &token);
+            assert_eq!(
+                snapshot.iter().collect::<Vec<_>>(),
+                Node::view_as_vec(&list, &token)
+            );
+            list.borrow_mut(&mut token).data = 10;
+            snapshot
+        });
+        // the snapshot outlives the token and isn't affected by later writes
+        assert_eq!(*snapshot, [0, 1, 2, 3]);
+    }
+
+    pub fn run_all_examples() {
+        list_wrapper_usage();
+        view_as_vec();
+        iter_with_index();
+        max_and_min_by_key();
+        fused_iter();
+        for_each_pair();
+        binary_search();
+        chunk_view();
+        windows_view();
+        self_insertion();
+        reverse_keeps_nodes();
+        empty_list_wrapper();
+        remove_value();
+        rposition();
+        iter_rev();
+        distance();
+        fold_rev();
+        group_by();
+        splice();
+        list_wrapper_len();
+        list_wrapper_for_each_mut();
+        list_wrapper_search();
+        list_wrapper_get();
+        list_wrapper_remove_at();
+        list_wrapper_insert_at();
+        list_wrapper_hash();
+        is_sorted();
+        merge_sorted();
+        merge_sort();
+        fold_while();
+        any_and_all();
+        dedup_by_key();
+        step_by_view();
+        flat_map();
+        scan();
+        rotate_to_front();
+        lru_list();
+        strong_counts();
+        lists_are_freed();
+        to_vecdeque();
+        from_vecdeque();
+        collect_into();
+        apply_at();
+        replace_at();
+        rotate_right();
+        node_arena();
+        drop_long_list();
+        snapshot();
+        list_wrapper_split_off();
+        list_wrapper_retain();
+        list_wrapper_reverse();
+        list_wrapper_sort();
+        list_wrapper_map_collect();
+        detect_cycle();
+        nth_back();
+        from_iter();
+        new_chain();
+        into_vec();
+        list_wrapper_ord();
+        contains_node();
+        take_and_skip_while_view();
+        partition_point();
+        reduce_in_place();
+        enumerate_mut();
+        swap_adjacent();
+        view_as_vec_limited();
+        has_shared_nodes();
+    }
+}
+
+fn main() {
+    ownership::client_lib::run_all_examples();
+    dllist_client_lib::run_all_examples();
+}
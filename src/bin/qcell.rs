@@ -0,0 +1,176 @@
+pub mod client_lib {
+    use std::{cmp::Ordering, sync::Arc};
+
+    use qcell::{QCellOwner, QCellOwnerID};
+
+    use cells_demo::qcell::{Node, NodePtr};
+
+    pub fn simple_usage() {
+        let mut token = QCellOwner::new();
+        let list1 = Node::from_iter(&mut token, [1, 2, 3]);
+        println!("{:?}", Node::view_as_vec(list1.as_ref(), &token));
+    }
+
+    pub fn dynamic_owner_check() {
+        let mut token1 = QCellOwner::new();
+        let _list1 = Node::from_iter(&mut token1, [1, 2, 3]);
+        let _token2 = QCellOwner::new();
+
+        // panics, dynamic check of owner fails:
+        // println!("{:?}", list1.map(|l| l.ro(&token2).data))
+    }
+
+    pub fn self_insertion() {
+        let mut token = QCellOwner::new();
+        let list = Node::from_iter(&mut token, [1, 2, 3]).unwrap();
+
+        Node::insert_next(&list, Arc::clone(&list), &mut token);
+        assert_eq!(Node::view_as_vec(Some(&list), &token), [&1, &2, &3]);
+    }
+
+    pub fn for_each_rev() {
+        let mut token = QCellOwner::new();
+        let list = Node::from_iter(&mut token, [1, 2, 3]).unwrap();
+        let tail = Node::tail(&list, &token);
+
+        let mut visited = vec![];
+        Node::for_each_rev(&tail, &token, |x| visited.push(*x));
+        assert_eq!(visited, [3, 2, 1]);
+    }
+
+    pub fn forward_and_backward_len() {
+        let mut token = QCellOwner::new();
+        let list = Node::from_iter(&mut token, [1, 2, 3, 4]).unwrap();
+        let tail = Node::tail(&list, &token);
+        assert_eq!(tail.ro(&token).data, 4);
+        assert_eq!(Node::len(&list, &token), 4);
+        assert_eq!(Node::len_rev(&tail, &token), 4);
+
+        let middle = Arc::clone(list.ro(&token).next().unwrap());
+        Node::remove(&middle, &mut token);
+        Node::insert_next(&tail, middle, &mut token);
+        let tail = Node::tail(&list, &token);
+        assert_eq!(Node::view_as_vec(Some(&list), &token), [&1, &3, &4, &2]);
+        assert_eq!(Node::len(&list, &token), Node::len_rev(&tail, &token));
+    }
+
+    pub fn into_vec() {
+        let mut token = QCellOwner::new();
+        let list = Node::from_iter(&mut token, [1, 2, 3]);
+        assert_eq!(Node::into_vec(list, &mut token), vec![1, 2, 3]);
+        assert!(Node::<i32>::into_vec(None, &mut token).is_empty());
+
+        let list = Node::from_iter(&mut token, [1, 2, 3]).unwrap();
+        let tail = Node::tail(&list, &token);
+        assert_eq!(Node::into_vec(Some(list), &mut token), vec![1, 2]);
+        assert_eq!(tail.ro(&token).data, 3);
+        assert_eq!(Node::len_rev(&tail, &token), 1);
+    }
+
+    pub fn retain() {
+        let mut token = QCellOwner::new();
+
+        let list = Node::from_iter(&mut token, [1, 2, 3, 4, 5]);
+        let odds = Node::retain(list, &mut token, |x| x % 2 == 1);
+        assert_eq!(Node::view_as_vec(odds.as_ref(), &token), [&1, &3, &5]);
+
+        let list = Node::from_iter(&mut token, [1, 2, 3]);
+        let rest = Node::retain(list, &mut token, |&x| x != 1).unwrap();
+        assert_eq!(Node::view_as_vec(Some(&rest), &token), [&2, &3]);
+        assert_eq!(Node::len_rev(&Node::tail(&rest, &token), &token), 2);
+
+        let list = Node::from_iter(&mut token, [1, 2, 3]);
+        assert!(Node::retain(list, &mut token, |_| false).is_none());
+    }
+
+    pub fn sort() {
+        let mut token = QCellOwner::new();
+        for input in [
+            vec![3, 1, 4, 1, 5, 9, 2, 6],
+            vec![1, 2, 3, 4],
+            vec![4, 3, 2, 1],
+            vec![7],
+        ] {
+            let mut expected = input.clone();
+            expected.sort();
+
+            let list = Node::from_iter(&mut token, input);
+            let sorted = Node::sort(list, &mut token).unwrap();
+            assert_eq!(
+                Node::view_as_vec(Some(&sorted), &token),
+                expected.iter().collect::<Vec<_>>()
+            );
+            let tail = Node::tail(&sorted, &token);
+            assert_eq!(Node::len(&sorted, &token), Node::len_rev(&tail, &token));
+        }
+        assert!(Node::<i32>::sort(None, &mut token).is_none());
+
+        // compared by `key` only, so elements with equal keys have to keep their order
+        struct Tagged {
+            key: i32,
+            tag: char,
+        }
+        impl PartialEq for Tagged {
+            fn eq(&self, other: &Self) -> bool {
+                self.key == other.key
+            }
+        }
+        impl Eq for Tagged {}
+        impl PartialOrd for Tagged {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+        impl Ord for Tagged {
+            fn cmp(&self, other: &Self) -> Ordering {
+                self.key.cmp(&other.key)
+            }
+        }
+        let input = [(2, 'a'), (1, 'b'), (2, 'c'), (1, 'd'), (2, 'e')];
+        let list = Node::from_iter(&mut token, input.map(|(key, tag)| Tagged { key, tag }));
+        let sorted = Node::sort(list, &mut token).unwrap();
+        let tags = Node::view_as_vec(Some(&sorted), &token)
+            .into_iter()
+            .map(|x| x.tag)
+            .collect::<String>();
+        assert_eq!(tags, "bdace");
+    }
+
+    /// `QCell` keeps the ID of its owner private, so a node can't tell which owner it
+    /// belongs to. When lists under different owners are kept in one collection, store
+    /// the owner's `QCellOwnerID` next to each node and use it to pick the right owner
+    /// before reading.
+    pub fn owner_routing() {
+        let mut owners = [QCellOwner::new(), QCellOwner::new()];
+        let mut nodes: Vec<(NodePtr<i32>, QCellOwnerID)> = vec![];
+        for (owner, values) in owners.iter_mut().zip([[1, 2], [10, 20]]) {
+            let list = Node::from_iter(owner, values).unwrap();
+            nodes.push((Node::tail(&list, owner), owner.id()));
+            nodes.push((list, owner.id()));
+        }
+        assert!(nodes[0].1 != nodes[2].1);
+
+        let mut sum = 0;
+        for (node, id) in &nodes {
+            let owner = owners.iter().find(|owner| owner.id() == *id).unwrap();
+            sum += node.ro(owner).data;
+        }
+        assert_eq!(sum, 33);
+    }
+
+    pub fn run_all_examples() {
+        simple_usage();
+        dynamic_owner_check();
+        self_insertion();
+        for_each_rev();
+        forward_and_backward_len();
+        into_vec();
+        retain();
+        sort();
+        owner_routing();
+    }
+}
+
+fn main() {
+    client_lib::run_all_examples();
+}
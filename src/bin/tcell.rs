@@ -0,0 +1,332 @@
+mod client_lib {
+    use std::{panic, sync::Arc};
+
+    use qcell::TCellOwner;
+
+    use cells_demo::tcell::{Node, NodePtr, TDeque, TRingBuffer};
+
+    pub fn simple_usage() {
+        struct Brand;
+        let mut token = TCellOwner::<Brand>::new();
+        let list1 = Node::from_iter(&mut token, [1, 2, 3]);
+        println!("{:?}", Node::view_as_vec(list1.as_ref(), &token));
+    }
+
+    pub fn unique_owner_restriction() {
+        struct Brand;
+        let mut token1 = TCellOwner::<Brand>::new();
+        let _list1 = Node::from_iter(&mut token1, [1, 2, 3]);
+        // will panic:
+        // let token2 = TCellOwner::<Brand>::new();
+    }
+
+    pub fn static_owner_check() {
+        struct Brand;
+        let mut token1 = TCellOwner::<Brand>::new();
+        let _list1 = Node::from_iter(&mut token1, [1, 2, 3]);
+
+        // does not compile
+        // struct Brand2;
+        // let token2 = TCellOwner::<Brand2>::new();
+        // println!("{:?}", list1.map(|l| l.ro(&token2).data))
+    }
+
+    pub fn two_simultaneous_borrows() {
+        struct Brand;
+        let mut token = TCellOwner::<Brand>::new();
+        let first = Node::from_iter(&mut token, [1, 2]).unwrap();
+        let second = Arc::clone(first.ro(&token).next().unwrap());
+        let (first_ref, second_ref) = token.rw2(&first, &second);
+
+        first_ref.data = 61;
+        second_ref.data = 62;
+        println!("{:?}", Node::view_as_vec(Option::Some(&first), &token));
+    }
+
+    pub fn two_simultaneous_borrows_panic() {
+        // 2 simultaneous borrows panic as references point to one cell
+        struct Brand;
+        let mut token = TCellOwner::<Brand>::new();
+        let first = Node::from_iter(&mut token, [1]).unwrap();
+        let _second = Arc::clone(&first);
+        // panics:
+        // let (first_ref, second_ref) = token.rw2(&first, &second);
+    }
+
+    pub fn two_structs_in_one_vector_fail() {
+        #[allow(dead_code)]
+        trait Brand {}
+
+        struct Brand1;
+        let mut token1 = TCellOwner::<Brand1>::new();
+        let _first = Node::from_iter(&mut token1, [1, 2, 3]);
+
+        struct Brand2;
+        let mut token2 = TCellOwner::<Brand2>::new();
+        let _second = Node::from_iter(&mut token2, [1, 2, 3]);
+
+        // does not compile:
+        // struct MultipleListsContainer<T> {
+        //     lists: Vec<Option<NodePtr<T, dyn Brand>>>,
+        // }
+    }
+
+    pub fn self_insertion() {
+        struct Brand;
+        let mut token = TCellOwner::<Brand>::new();
+        let first = Node::from_iter(&mut token, [1, 2, 3]).unwrap();
+        let second = Arc::clone(first.ro(&token).next().unwrap());
+
+        Node::insert_next(&second, Arc::clone(&second), &mut token);
+        assert_eq!(Node::view_as_vec(Some(&first), &token), [&1, &2, &3]);
+    }
+
+    pub fn truncate() {
+        struct Brand;
+        let mut token = TCellOwner::<Brand>::new();
+        let list = Node::from_iter(&mut token, [1, 2, 3, 4, 5]).unwrap();
+
+        Node::truncate(&list, 7, &mut token);
+        assert_eq!(Node::view_as_vec(Some(&list), &token), [&1, &2, &3, &4, &5]);
+        Node::truncate(&list, 3, &mut token);
+        assert_eq!(Node::view_as_vec(Some(&list), &token), [&1, &2, &3]);
+        Node::truncate(&list, 1, &mut token);
+        assert_eq!(Node::view_as_vec(Some(&list), &token), [&1]);
+    }
+
+    pub fn extend() {
+        struct Brand;
+        let mut token = TCellOwner::<Brand>::new();
+        let list = Node::from_iter(&mut token, [1, 2]).unwrap();
+
+        let tail = Node::extend(&list, [3, 4, 5], &mut token);
+        assert_eq!(Node::view_as_vec(Some(&list), &token), [&1, &2, &3, &4, &5]);
+        assert_eq!(tail.ro(&token).data, 5);
+        assert!(tail.ro(&token).next().is_none());
+    }
+
+    pub fn rev_view_as_vec() {
+        struct Brand;
+        let mut token = TCellOwner::<Brand>::new();
+        let list = Node::from_iter(&mut token, [1, 2, 3, 4]).unwrap();
+        let tail = Node::tail(&list, &token);
+
+        let backward: Vec<i32> = Node::rev_view_as_vec(&tail, &token)
+            .iter()
+            .map(|node| node.ro(&token).data)
+            .collect();
+        let mut forward: Vec<&i32> = Node::view_as_vec(Some(&list), &token);
+        forward.reverse();
+        assert_eq!(backward.iter().collect::<Vec<_>>(), forward);
+    }
+
+    pub fn tail() {
+        struct Brand;
+        let mut token = TCellOwner::<Brand>::new();
+        let list = Node::from_iter(&mut token, [1, 2, 3]).unwrap();
+
+        let tail = Node::tail(&list, &token);
+        assert_eq!(tail.ro(&token).data, 3);
+        assert!(Arc::ptr_eq(&Node::tail(&tail, &token), &tail));
+    }
+
+    pub fn map() {
+        struct Brand;
+        let mut token = TCellOwner::<Brand>::new();
+        let list = Node::from_iter(&mut token, [1, 2, 3, -4]).unwrap();
+
+        let squares = Node::map(Some(&list), &mut token, |x: &i32| x * x);
+        assert_eq!(
+            Node::view_as_vec(squares.as_ref(), &token),
+            [&1, &4, &9, &16]
+        );
+        assert_eq!(Node::view_as_vec(Some(&list), &token), [&1, &2, &3, &-4]);
+        assert!(Node::map(None::<&NodePtr<i32, Brand>>, &mut token, |x| x * x).is_none());
+    }
+
+    pub fn clone_list() {
+        struct Brand;
+        let mut token = TCellOwner::<Brand>::new();
+        let list = Node::from_iter(&mut token, [1, 2, 3]).unwrap();
+
+        let copy = Node::clone_list(Some(&list), &mut token).unwrap();
+        assert!(!Arc::ptr_eq(&list, &copy));
+        copy.rw(&mut token).data = 10;
+        Node::truncate(&copy, 2, &mut token);
+        assert_eq!(Node::view_as_vec(Some(&copy), &token), [&10, &2]);
+        assert_eq!(Node::view_as_vec(Some(&list), &token), [&1, &2, &3]);
+    }
+
+    pub fn remove_value() {
+        struct Brand;
+        let mut token = TCellOwner::<Brand>::new();
+        let list = Node::from_iter(&mut token, [0, 1, 2]).unwrap();
+        let middle = Arc::clone(list.ro(&token).next().unwrap());
+
+        assert_eq!(Node::remove_value(middle, &mut token), Some(1));
+        assert_eq!(Node::view_as_vec(Some(&list), &token), [&0, &2]);
+
+        // `tail` is still alive, so the data can't be moved out
+        let tail = Node::tail(&list, &token);
+        assert_eq!(Node::remove_value(Arc::clone(&tail), &mut token), None);
+        assert_eq!(Node::view_as_vec(Some(&list), &token), [&0]);
+        assert_eq!(tail.ro(&token).data, 2);
+    }
+
+    pub fn swap_remove() {
+        struct Brand;
+        let mut token = TCellOwner::<Brand>::new();
+        let list = Node::from_iter(&mut token, [1, 2, 3, 4]).unwrap();
+        let second = Arc::clone(list.ro(&token).next().unwrap());
+
+        assert_eq!(Node::swap_remove(&list, second, &mut token), Some(2));
+        let mut rest: Vec<i32> = Node::view_as_vec(Some(&list), &token)
+            .into_iter()
+            .copied()
+            .collect();
+        assert_eq!(rest, [1, 4, 3]);
+        rest.sort();
+        assert_eq!(rest, [1, 3, 4]);
+
+        let tail = Node::tail(&list, &token);
+        assert_eq!(Node::swap_remove(&list, tail, &mut token), Some(3));
+        assert_eq!(Node::view_as_vec(Some(&list), &token), [&1, &4]);
+
+        // the tail is kept alive from outside the list, so nothing can be moved out of it
+        let tail = Node::tail(&list, &token);
+        assert_eq!(
+            Node::swap_remove(&list, Arc::clone(&list), &mut token),
+            None
+        );
+        assert_eq!(Node::view_as_vec(Some(&list), &token), [&1, &4]);
+        drop(tail);
+        assert_eq!(
+            Node::swap_remove(&list, Arc::clone(&list), &mut token),
+            Some(1)
+        );
+        assert_eq!(Node::view_as_vec(Some(&list), &token), [&4]);
+    }
+
+    pub fn ring_buffer() {
+        struct Brand;
+        let mut buffer = TRingBuffer::new(TCellOwner::<Brand>::new(), 3);
+        for x in 1..=3 {
+            assert_eq!(buffer.push(x), None);
+        }
+        assert_eq!(buffer.iter().collect::<Vec<_>>(), [&1, &2, &3]);
+
+        assert_eq!(buffer.push(4), Some(1));
+        assert_eq!(buffer.push(5), Some(2));
+        assert_eq!(buffer.iter().collect::<Vec<_>>(), [&3, &4, &5]);
+        assert_eq!(buffer.len(), buffer.capacity());
+        for x in 6..=10 {
+            buffer.push(x);
+        }
+        assert_eq!(buffer.iter().collect::<Vec<_>>(), [&8, &9, &10]);
+        assert!(!buffer.is_empty());
+
+        struct Brand2;
+        let mut single = TRingBuffer::new(TCellOwner::<Brand2>::new(), 1);
+        assert_eq!(single.push('a'), None);
+        assert_eq!(single.push('b'), Some('a'));
+        assert_eq!(single.iter().collect::<Vec<_>>(), [&'b']);
+    }
+
+    pub fn drop_long_ring_buffer() {
+        struct Brand;
+        // freed node by node rather than recursively, so this doesn't overflow the stack
+        let mut buffer = TRingBuffer::new(TCellOwner::<Brand>::new(), 100_000);
+        for x in 0..100_000 {
+            buffer.push(x);
+        }
+        drop(buffer);
+    }
+
+    pub fn tdeque_usage() {
+        struct Brand;
+        let mut deque = TDeque::new(TCellOwner::<Brand>::new());
+        deque.push_back(2);
+        deque.push_back(3);
+        deque.push_front(1);
+        assert_eq!(deque.len(), 3);
+        assert_eq!(deque.iter().collect::<Vec<_>>(), [&1, &2, &3]);
+
+        assert_eq!(deque.pop_front(), Some(1));
+        assert_eq!(deque.pop_back(), Some(3));
+        assert_eq!(deque.pop_back(), Some(2));
+        assert_eq!(deque.pop_front(), None);
+        assert_eq!(deque.pop_back(), None);
+        assert!(deque.is_empty());
+
+        deque.push_front(4);
+        println!("{:?}", deque.iter().collect::<Vec<_>>());
+    }
+
+    pub fn tdeque_unique_owner() {
+        struct Brand;
+        let _deque = TDeque::<i32, Brand>::new(TCellOwner::new());
+        // the deque holds the only owner of the brand, so no second one can be made
+        assert!(TCellOwner::<Brand>::try_new().is_none());
+
+        // and `new` panics instead; keep the expected panic from being printed
+        let hook = panic::take_hook();
+        panic::set_hook(Box::new(|_| {}));
+        let second = panic::catch_unwind(TCellOwner::<Brand>::new);
+        panic::set_hook(hook);
+        assert!(second.is_err());
+    }
+
+    /// Lists of different brands can't be put into one `Vec`, but generic code can
+    /// still work on several of them as long as each comes with its own owner.
+    fn sum_two_brands<Brand1, Brand2>(
+        first: Option<&NodePtr<i32, Brand1>>,
+        token1: &TCellOwner<Brand1>,
+        second: Option<&NodePtr<i32, Brand2>>,
+        token2: &TCellOwner<Brand2>,
+    ) -> i32 {
+        let first_sum: i32 = Node::view_as_vec(first, token1).into_iter().sum();
+        let second_sum: i32 = Node::view_as_vec(second, token2).into_iter().sum();
+        first_sum + second_sum
+    }
+
+    pub fn two_brands_side_by_side() {
+        struct Brand1;
+        let mut token1 = TCellOwner::<Brand1>::new();
+        let first = Node::from_iter(&mut token1, [1, 2, 3]);
+
+        struct Brand2;
+        let mut token2 = TCellOwner::<Brand2>::new();
+        let second = Node::from_iter(&mut token2, [10, 20]);
+
+        let sum = sum_two_brands(first.as_ref(), &token1, second.as_ref(), &token2);
+        assert_eq!(sum, 36);
+        println!("{}", sum);
+    }
+
+    pub fn run_all_examples() {
+        simple_usage();
+        unique_owner_restriction();
+        static_owner_check();
+        two_simultaneous_borrows();
+        two_simultaneous_borrows_panic();
+        two_structs_in_one_vector_fail();
+        tdeque_usage();
+        tdeque_unique_owner();
+        self_insertion();
+        two_brands_side_by_side();
+        truncate();
+        extend();
+        rev_view_as_vec();
+        tail();
+        map();
+        clone_list();
+        remove_value();
+        swap_remove();
+        ring_buffer();
+        drop_long_ring_buffer();
+    }
+}
+
+fn main() {
+    client_lib::run_all_examples();
+}
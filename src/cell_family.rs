@@ -1,6 +1,11 @@
-use std::{fmt::Debug, rc::Rc};
+#[cfg(debug_assertions)]
+use std::cell::Cell;
+use std::{
+    fmt::{Debug, Write},
+    rc::Rc,
+};
 
-cell_family::define!(type FooFamily: FooCellOwner for FooCell<T>);
+cell_family::define!(pub type FooFamily: FooCellOwner for FooCell<T>);
 
 struct Node<T> {
     data: T,
@@ -18,13 +23,31 @@ impl<T> Node<T> {
     }
 }
 
-struct Deque<T> {
+pub struct Deque<T> {
     head: Option<Rc<FooCell<Node<T>>>>,
     tail: Option<Rc<FooCell<Node<T>>>>,
     owner: FooCellOwner,
+    /// How many nodes `iter` has visited, to check which operations walk the list.
+    #[cfg(debug_assertions)]
+    visits: Cell<usize>,
 }
 
 impl<T> Deque<T> {
+    /// Creates an empty deque, panicking if another `FooCellOwner` is alive.
+    #[allow(
+        clippy::new_without_default,
+        reason = "`Default` shouldn't panic, and `new` does while another deque is alive"
+    )]
+    pub fn new() -> Self {
+        Deque {
+            head: Option::None,
+            tail: Option::None,
+            owner: FooCellOwner::new(),
+            #[cfg(debug_assertions)]
+            visits: Cell::new(0),
+        }
+    }
+
     fn add_to_empty(&mut self, x: Node<T>) {
         let node = Rc::new(FooCell::new(x));
         self.head = Option::Some(node.clone());
@@ -63,63 +86,347 @@ impl<T> Deque<T> {
         }
     }
 
-    fn as_vec(&self) -> Vec<&T> {
-        let mut elements = Vec::<&T>::new();
-        let mut next = &self.head;
-        while let Some(node) = next {
-            elements.push(&node.get(&self.owner).data);
-            next = &node.get(&self.owner).next;
+    /// The first element, read straight through `head`.
+    pub fn front(&self) -> Option<&T> {
+        self.head.as_ref().map(|node| &node.get(&self.owner).data)
+    }
+
+    /// The last element, read straight through `tail` without walking the list.
+    pub fn back(&self) -> Option<&T> {
+        self.tail.as_ref().map(|node| &node.get(&self.owner).data)
+    }
+
+    /// A mutable reference to the element at `index`, or `None` if it is out of range.
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        // Walking the list borrows `self.owner` for every step, which would rule out the
+        // mutable borrow of the owner at the end, so the walk goes through a raw pointer.
+        let mut node: *const FooCell<Node<T>> = &**self.head.as_ref()?;
+        for _ in 0..index {
+            // SAFETY: `node` points into the `Rc` allocation held by `head` or by the `next`
+            // link of the node before it. The walk only reads links through a shared borrow
+            // of `self.owner`, and nothing else can unlink or free a node while `self` is
+            // mutably borrowed, so the node is alive and no `&mut` to it exists.
+            node = &**unsafe { &*node }.get(&self.owner).next.as_ref()?;
+        }
+        // SAFETY: as in the loop, the node is kept alive by the `next` links from `head`.
+        // Those links can only change through `&mut self`, and the returned reference borrows
+        // `self.owner` and with it `self` for its whole lifetime, so the node outlives it.
+        // `get_mut` takes the owner mutably, which rules out any other borrow of a cell of
+        // the family while the returned reference exists.
+        Option::Some(&mut unsafe { &*node }.get_mut(&mut self.owner).data)
+    }
+
+    pub fn pop_first(&mut self) -> Option<T> {
+        self.unlink_first().map(Self::into_data)
+    }
+
+    pub fn pop_last(&mut self) -> Option<T> {
+        self.unlink_last().map(Self::into_data)
+    }
+
+    /// Insert `x` so that it ends up at `index`, shifting the later elements back.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the length of the deque.
+    pub fn insert(&mut self, index: usize, x: T) {
+        let len = self.iter().count();
+        assert!(
+            index <= len,
+            "insertion index {index} out of range for length {len}"
+        );
+        if index == 0 {
+            return self.add_first(x);
+        }
+        if index == len {
+            return self.add_last(x);
+        }
+        let mut before = self.head.clone().unwrap();
+        for _ in 1..index {
+            before = before.get(&self.owner).next.clone().unwrap();
+        }
+        let after = before.get(&self.owner).next.clone().unwrap();
+
+        let mut node = Node::new(x);
+        node.previous = Option::Some(before.clone());
+        node.next = Option::Some(after.clone());
+        let node = Rc::new(FooCell::new(node));
+        before.get_mut(&mut self.owner).next = Option::Some(node.clone());
+        after.get_mut(&mut self.owner).previous = Option::Some(node);
+    }
+
+    /// Remove the element at `index` and return it, or `None` if `index` is out of range.
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        let len = self.iter().count();
+        if index >= len {
+            return Option::None;
+        }
+        if index == 0 {
+            return self.pop_first();
+        }
+        if index == len - 1 {
+            return self.pop_last();
+        }
+        let mut node = self.head.clone().unwrap();
+        for _ in 0..index {
+            node = node.get(&self.owner).next.clone().unwrap();
+        }
+        let inner = node.get_mut(&mut self.owner);
+        let before = inner.previous.take().unwrap();
+        let after = inner.next.take().unwrap();
+        before.get_mut(&mut self.owner).next = Option::Some(after.clone());
+        after.get_mut(&mut self.owner).previous = Option::Some(before);
+        Option::Some(Self::into_data(node))
+    }
+
+    /// Drop everything after the first `len` elements. A no-op if the deque isn't longer.
+    pub fn truncate(&mut self, len: usize) {
+        let count = self.iter().count();
+        for _ in len..count {
+            // fully unlinked, so the node is freed right away despite the strong `previous` links
+            self.unlink_last();
         }
-        elements
+    }
+
+    pub fn clear(&mut self) {
+        self.truncate(0);
+    }
+
+    /// Move the first `n % len` elements to the back by relinking their nodes.
+    pub fn rotate_left(&mut self, n: usize) {
+        let len = self.iter().count();
+        if len == 0 {
+            return;
+        }
+        for _ in 0..n % len {
+            let node = self.unlink_first().unwrap();
+            self.link_last(node);
+        }
+    }
+
+    /// Move the last `n % len` elements to the front by relinking their nodes.
+    pub fn rotate_right(&mut self, n: usize) {
+        let len = self.iter().count();
+        if len == 0 {
+            return;
+        }
+        for _ in 0..n % len {
+            let node = self.unlink_last().unwrap();
+            self.link_first(node);
+        }
+    }
+
+    fn unlink_first(&mut self) -> Option<Rc<FooCell<Node<T>>>> {
+        let old_head = self.head.take()?;
+        match old_head.get_mut(&mut self.owner).next.take() {
+            Option::None => self.tail = Option::None,
+            Option::Some(new_head) => {
+                new_head.get_mut(&mut self.owner).previous = Option::None;
+                self.head = Option::Some(new_head);
+            }
+        }
+        Option::Some(old_head)
+    }
+
+    fn unlink_last(&mut self) -> Option<Rc<FooCell<Node<T>>>> {
+        let old_tail = self.tail.take()?;
+        match old_tail.get_mut(&mut self.owner).previous.take() {
+            Option::None => self.head = Option::None,
+            Option::Some(new_tail) => {
+                new_tail.get_mut(&mut self.owner).next = Option::None;
+                self.tail = Option::Some(new_tail);
+            }
+        }
+        Option::Some(old_tail)
+    }
+
+    /// Link a detached node in front of the head.
+    fn link_first(&mut self, node: Rc<FooCell<Node<T>>>) {
+        match self.head.take() {
+            Option::None => self.tail = Option::Some(node.clone()),
+            Option::Some(old_head) => {
+                old_head.get_mut(&mut self.owner).previous = Option::Some(node.clone());
+                node.get_mut(&mut self.owner).next = Option::Some(old_head);
+            }
+        }
+        self.head = Option::Some(node);
+    }
+
+    /// Link a detached node after the tail.
+    fn link_last(&mut self, node: Rc<FooCell<Node<T>>>) {
+        match self.tail.take() {
+            Option::None => self.head = Option::Some(node.clone()),
+            Option::Some(old_tail) => {
+                old_tail.get_mut(&mut self.owner).next = Option::Some(node.clone());
+                node.get_mut(&mut self.owner).previous = Option::Some(old_tail);
+            }
+        }
+        self.tail = Option::Some(node);
+    }
+
+    fn into_data(node: Rc<FooCell<Node<T>>>) -> T {
+        crate::unwrap_unlinked(Rc::try_unwrap(node))
+            .into_inner()
+            .data
+    }
+
+    pub fn iter(&self) -> DequeIter<'_, T> {
+        DequeIter {
+            next: &self.head,
+            owner: &self.owner,
+            #[cfg(debug_assertions)]
+            visits: &self.visits,
+        }
+    }
+
+    /// The number of nodes visited by iterating so far. Only counted in debug builds.
+    #[cfg(debug_assertions)]
+    pub fn node_visits(&self) -> usize {
+        self.visits.get()
+    }
+
+    /// Move all elements of `other` onto the end of `self`, leaving `other` empty.
+    ///
+    /// `other` can't be another `Deque`: every `Deque` holds a `FooCellOwner` and only one
+    /// of those can be alive per thread, so two deques never exist at the same time.
+    pub fn append(&mut self, other: &mut Vec<T>) {
+        for x in other.drain(..) {
+            self.add_last(x);
+        }
+    }
+
+    /// Yield the elements front to back by value. The whole chain is detached from the
+    /// deque up front, so it is left empty even if the iterator is dropped before the end.
+    pub fn drain(&mut self) -> Drain<'_, T> {
+        self.tail = Option::None;
+        Drain {
+            next: self.head.take(),
+            owner: &mut self.owner,
+        }
+    }
+
+    /// One line per node with the address of the node and of its `previous` and `next`
+    /// neighbours, for tracking down broken links.
+    pub fn debug_structure(&self) -> String
+    where
+        T: Debug,
+    {
+        let address = |link: &Option<Rc<FooCell<Node<T>>>>| match link {
+            Option::Some(node) => format!("{:p}", Rc::as_ptr(node)),
+            Option::None => "None".to_string(),
+        };
+        let mut out = String::new();
+        let mut cur = &self.head;
+        while let Option::Some(node) = cur {
+            let inner = node.get(&self.owner);
+            writeln!(
+                out,
+                "{:p}: {:?} (previous: {}, next: {})",
+                Rc::as_ptr(node),
+                inner.data,
+                address(&inner.previous),
+                address(&inner.next),
+            )
+            .unwrap();
+            cur = &inner.next;
+        }
+        out
+    }
+
+    pub fn as_vec(&self) -> Vec<&T> {
+        self.iter().collect()
     }
 }
 
-impl<T: Debug> Debug for Deque<T> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        self.as_vec().fmt(f)
+pub struct DequeIter<'a, T> {
+    next: &'a Option<Rc<FooCell<Node<T>>>>,
+    owner: &'a FooCellOwner,
+    #[cfg(debug_assertions)]
+    visits: &'a Cell<usize>,
+}
+
+impl<'a, T> Iterator for DequeIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.next.as_ref()?.get(self.owner);
+        #[cfg(debug_assertions)]
+        self.visits.set(self.visits.get() + 1);
+        self.next = &node.next;
+        Option::Some(&node.data)
     }
 }
 
-fn two_aliases_example() {
-    #[derive(Debug)]
-    struct MyStruct {
-        data: usize,
-    }
-    // cells usage
-    cell_family::define!(type XFamily: XCellOwner for XCell<T>);
-    let mut owner = XCellOwner::new();
-    let cell = XCell::new(MyStruct { data: 123 });
-    let ref1 = Rc::new(cell);
-    let ref2 = ref1.clone();
-    {
-        // mutation through ref1
-        ref1.get_mut(&mut owner).data = 35;
-        assert_eq!(ref1.get(&owner).data, 35);
-        assert_eq!(ref2.get(&owner).data, 35);
+pub struct Drain<'a, T> {
+    next: Option<Rc<FooCell<Node<T>>>>,
+    owner: &'a mut FooCellOwner,
+}
+
+impl<T> Iterator for Drain<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.next.take()?;
+        self.next = node.get_mut(self.owner).next.take();
+        if let Option::Some(next) = &self.next {
+            next.get_mut(self.owner).previous = Option::None;
+        }
+        Option::Some(Deque::into_data(node))
     }
-    {
-        // mutation through ref2
-        ref2.get_mut(&mut owner).data = 42;
-        assert_eq!(ref1.get(&owner).data, 42);
-        assert_eq!(ref2.get(&owner).data, 42);
+}
+
+/// The rest of the detached chain is still linked both ways, so it has to be unlinked
+/// node by node like in `Deque`'s `Drop`.
+impl<T> Drop for Drain<'_, T> {
+    fn drop(&mut self) {
+        for _ in self.by_ref() {}
     }
 }
 
-fn deque_example() {
-    // Caution: given deque can only have one instance because its type marker is fixed
-    // This is bad, deque should be parametrized by the type marker, see qcell and tcell examples
-    let mut deque = Deque::<usize> {
-        head: Option::None,
-        tail: Option::None,
-        owner: FooCellOwner::new(),
-    };
-    deque.add_first(2);
-    deque.add_first(1);
-    deque.add_last(3);
-    println!("{:?}", deque);
+/// Every node is its own `Rc` allocation, so unlike `Vec` there is nothing to preallocate
+/// from the size hint. `add_last` links through the stored `tail`, so collecting is
+/// linear anyway.
+impl<T> FromIterator<T> for Deque<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(elements: I) -> Self {
+        let mut deque = Deque::new();
+        for x in elements {
+            deque.add_last(x);
+        }
+        deque
+    }
 }
 
-fn main() {
-    deque_example();
-    two_aliases_example();
+/// The `previous` links are strong too, so every pair of neighbours is an `Rc` cycle that
+/// would leak if the nodes weren't unlinked first.
+impl<T> Drop for Deque<T> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<T> From<Vec<T>> for Deque<T> {
+    fn from(elements: Vec<T>) -> Self {
+        elements.into_iter().collect()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Deque<T> {
+    type Item = &'a T;
+    type IntoIter = DequeIter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T: Clone> Deque<T> {
+    pub fn to_vec(&self) -> Vec<T> {
+        self.as_vec().into_iter().cloned().collect()
+    }
+}
+
+impl<T: Debug> Debug for Deque<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.as_vec().fmt(f)
+    }
 }
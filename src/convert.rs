@@ -0,0 +1,27 @@
+//! Conversions between the list backends.
+
+use ::ghost_cell::GhostToken;
+use ::qcell::{QCellOwner, TCellOwner};
+
+use crate::{cell_family::Deque, ghost_cell, qcell, tcell};
+
+/// Build a TCell list with the same elements as the ghost_cell list starting at `head`.
+pub fn ghost_to_tcell<'id, T: Clone, Brand>(
+    head: &ghost_cell::NodePtr<'id, T>,
+    ghost_token: &GhostToken<'id>,
+    tcell_token: &mut TCellOwner<Brand>,
+) -> Option<tcell::NodePtr<T, Brand>> {
+    tcell::Node::from_iter(
+        tcell_token,
+        ghost_cell::Node::iter(head, ghost_token).cloned(),
+    )
+}
+
+/// Copy the QCell list starting at `head` into a `Deque`, trading the dynamic owner
+/// check for the statically unique `FooCellOwner`.
+pub fn qcell_to_deque<T: Clone>(head: Option<&qcell::NodePtr<T>>, owner: &QCellOwner) -> Deque<T> {
+    qcell::Node::view_as_vec(head, owner)
+        .into_iter()
+        .cloned()
+        .collect()
+}
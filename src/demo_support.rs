@@ -0,0 +1,23 @@
+//! Helpers shared by the demo binaries. Not part of the lists' API.
+
+use alloc::rc::Rc;
+use core::cell::Cell;
+
+/// Counts how many of the values sharing a counter have been dropped.
+pub struct DropCounter {
+    drops: Rc<Cell<usize>>,
+}
+
+impl DropCounter {
+    pub fn new(drops: &Rc<Cell<usize>>) -> Self {
+        Self {
+            drops: Rc::clone(drops),
+        }
+    }
+}
+
+impl Drop for DropCounter {
+    fn drop(&mut self) {
+        self.drops.set(self.drops.get() + 1);
+    }
+}
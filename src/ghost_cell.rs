@@ -1,29 +1,78 @@
-use ghost_cell::{GhostCell, GhostToken};
-use std::{
-    fmt::{Debug, Display, Pointer},
+use alloc::{
+    boxed::Box,
+    collections::VecDeque,
     sync::{Arc, Weak},
+    vec,
+    vec::Vec,
+};
+use core::{
+    cmp::Ordering,
+    fmt::Debug,
+    hash::{Hash, Hasher},
+    iter::{Enumerate, FusedIterator},
+    mem,
+    ops::{ControlFlow, Deref, DerefMut},
 };
+use ghost_cell::{GhostCell, GhostToken};
 
 /*
 This is not my implementation. Source: https://gitlab.mpi-sws.org/FP/ghostcell/-/blob/master/ghostcell/src/dlist_arc.rs
 */
 /// A doubly-linked list node.
+///
+/// `next` links are strong and `prev` links are weak, so a list never forms an `Arc` cycle:
+/// dropping the last pointer to the head frees the head, which frees its successor and so on.
+/// Only nodes that are still referenced from outside the list (e.g. a kept tail pointer)
+/// outlive it, together with everything after them.
 pub struct Node<'id, T> {
-    data: T,
+    pub data: T,
     prev: Option<WeakNodePtr<'id, T>>,
-    next: Option<NodePtr<'id, T>>,
+    next: Link<'id, T>,
 }
 /// A `Weak` pointer to a node.
 pub type WeakNodePtr<'id, T> = Weak<GhostCell<'id, Node<'id, T>>>;
 /// A strong `Arc` pointer to a node.
 pub type NodePtr<'id, T> = Arc<GhostCell<'id, Node<'id, T>>>;
 
+/// The strong link from a node to its successor.
+///
+/// Dropping a node drops its successor, which drops the next one and so on: for a long list
+/// that recursion overflows the stack. Dropping a `Link` frees the following nodes in a
+/// loop instead.
+struct Link<'id, T>(Option<NodePtr<'id, T>>);
+
+impl<'id, T> Deref for Link<'id, T> {
+    type Target = Option<NodePtr<'id, T>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<'id, T> DerefMut for Link<'id, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<'id, T> Drop for Link<'id, T> {
+    fn drop(&mut self) {
+        let mut next = self.0.take();
+        // a node that is still referenced elsewhere stays alive, and with it the rest
+        while let Some(node) = next {
+            next = Arc::try_unwrap(node)
+                .ok()
+                .and_then(|cell| cell.into_inner().next.take());
+        }
+    }
+}
+
 impl<'id, T> Node<'id, T> {
     pub fn new(value: T) -> NodePtr<'id, T> {
         Arc::new(GhostCell::new(Self {
             data: value,
             prev: None,
-            next: None,
+            next: Link(None),
         }))
     }
 
@@ -39,6 +88,19 @@ impl<'id, T> Node<'id, T> {
         self.next.as_ref()
     }
 
+    /// Replace the `next` link without touching the `prev` links of either neighbour.
+    ///
+    /// This leaves the list malformed, so it stays inside the crate; `with_cyclic_list`
+    /// uses it to build a cycle for `detect_cycle` to find.
+    pub(crate) fn overwrite_next(&mut self, next: Option<NodePtr<'id, T>>) {
+        *self.next = next;
+    }
+
+    /// Whether `a` and `b` point to the same node (identity, not value equality).
+    pub fn same(a: &NodePtr<'id, T>, b: &NodePtr<'id, T>) -> bool {
+        Arc::ptr_eq(a, b)
+    }
+
     /// Unlink the nodes adjacent to `node`. The node will have `next` and `prev` be `None` after this.
     pub fn remove<'a>(node: &NodePtr<'id, T>, token: &'a mut GhostToken<'id>) {
         // `take` both pointers from `node`, setting its fields to `None`.
@@ -47,19 +109,68 @@ impl<'id, T> Node<'id, T> {
         let old_next: Option<NodePtr<'id, T>> = node.next.take();
         // link `old_prev` and `old_next together
         if let Some(old_next) = &old_next {
-            old_next.borrow_mut(token).prev = old_prev.as_ref().map(|p| Arc::downgrade(&p));
+            old_next.borrow_mut(token).prev = old_prev.as_ref().map(Arc::downgrade);
         }
         if let Some(old_prev) = &old_prev {
-            old_prev.borrow_mut(token).next = old_next;
+            *old_prev.borrow_mut(token).next = old_next;
         }
     }
 
+    /// Unlink `node` like `remove` and return its data.
+    ///
+    /// The node is taken by value since its data can only be moved out of the last
+    /// strong reference. If the node is still aliased elsewhere it is unlinked anyway,
+    /// but `None` is returned and the data stays reachable through the other aliases.
+    pub fn remove_value(node: NodePtr<'id, T>, token: &mut GhostToken<'id>) -> Option<T> {
+        Self::remove(&node, token);
+        Arc::try_unwrap(node)
+            .ok()
+            .map(|cell| cell.into_inner().data)
+    }
+
+    /// Take the list apart head first and collect the data of its nodes, freeing each
+    /// node as it goes.
+    ///
+    /// Nodes that are still aliased elsewhere are unlinked too, but their data can't be
+    /// moved out: it is left out of the result and stays reachable through the aliases.
+    pub fn into_vec(head: NodePtr<'id, T>, token: &mut GhostToken<'id>) -> Vec<T> {
+        let mut v = vec![];
+        let mut cur = Some(head);
+        while let Some(node) = cur {
+            cur = node.borrow(token).next().cloned();
+            v.extend(Self::remove_value(node, token));
+        }
+        v
+    }
+
+    /// Fold every following element into `head` with `f`, freeing their nodes, and return
+    /// `head` as the only node left. Aliased nodes are unlinked but can't be given up by
+    /// value, so their elements are skipped, as in `into_vec`.
+    pub fn reduce_in_place(
+        head: NodePtr<'id, T>,
+        token: &mut GhostToken<'id>,
+        f: impl Fn(&mut T, T),
+    ) -> NodePtr<'id, T> {
+        while let Some(next) = head.borrow(token).next().cloned() {
+            if let Some(value) = Self::remove_value(next, token) {
+                f(&mut head.borrow_mut(token).data, value);
+            }
+        }
+        head
+    }
+
     /// Insert `node2` right after `node1` in the list.
+    /// Inserting a node after itself is a no-op.
     pub fn insert_next<'a>(
         node1: &NodePtr<'id, T>,
         node2: NodePtr<'id, T>,
         token: &'a mut GhostToken<'id>,
     ) {
+        // Step 0: unlinking node2 below would also unlink node1, so bail out.
+        if Self::same(node1, &node2) {
+            return;
+        }
+
         // Step 1: unlink the prev and next pointers of nodes that are
         // adjacent to node2.
         Self::remove(&node2, token);
@@ -73,10 +184,366 @@ impl<'id, T> Node<'id, T> {
         // Step 3: link node2 to node1 and node1_old_next.
         let node2_inner: &mut Node<'id, T> = node2.borrow_mut(token);
         node2_inner.prev = Some(Arc::downgrade(node1));
-        node2_inner.next = node1_old_next;
+        *node2_inner.next = node1_old_next;
 
         // Step 4: Link node1.next to node2.
-        node1.borrow_mut(token).next = Some(node2);
+        *node1.borrow_mut(token).next = Some(node2);
+    }
+
+    /// Insert `node2` right before `node1` in the list.
+    /// Inserting a node before itself is a no-op.
+    pub fn insert_prev(
+        node1: &NodePtr<'id, T>,
+        node2: NodePtr<'id, T>,
+        token: &mut GhostToken<'id>,
+    ) {
+        if Self::same(node1, &node2) {
+            return;
+        }
+        match node1.borrow(token).prev() {
+            Some(node1_prev) => Self::insert_next(&node1_prev, node2, token),
+            None => {
+                Self::remove(&node2, token);
+                node1.borrow_mut(token).prev = Some(Arc::downgrade(&node2));
+                *node2.borrow_mut(token).next = Some(Arc::clone(node1));
+            }
+        }
+    }
+
+    /// Swap `node` with its successor by relinking them, returning `false` (and doing
+    /// nothing) if `node` is the tail. If `node` was the head, the new head is its old
+    /// successor, now reachable as `node.prev()`.
+    pub fn swap_adjacent(node: &NodePtr<'id, T>, token: &mut GhostToken<'id>) -> bool {
+        match node.borrow(token).next().cloned() {
+            Some(next) => {
+                Self::insert_prev(node, next, token);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Move the first node equal to `value` in front of `head` (move-to-front), returning
+    /// the new head, or `None` if no node matches.
+    pub fn rotate_to_front(
+        head: &NodePtr<'id, T>,
+        value: &T,
+        token: &mut GhostToken<'id>,
+    ) -> Option<NodePtr<'id, T>>
+    where
+        T: PartialEq,
+    {
+        let mut cur = Arc::clone(head);
+        while cur.borrow(token).data != *value {
+            cur = cur.borrow(token).next().cloned()?;
+        }
+        Self::insert_prev(head, Arc::clone(&cur), token);
+        Some(cur)
+    }
+
+    /// Replace the inclusive range `start..=end` by the list starting at `replacement_head`,
+    /// or just cut the range out if there is no replacement. `start` must come before `end`
+    /// in the same list.
+    ///
+    /// Returns the node that now takes the place of `start`: the replacement head, or the
+    /// node that followed `end` if there is no replacement. This is the new head of the
+    /// list when `start` was the head.
+    pub fn splice(
+        start: &NodePtr<'id, T>,
+        end: &NodePtr<'id, T>,
+        replacement_head: Option<NodePtr<'id, T>>,
+        token: &mut GhostToken<'id>,
+    ) -> Option<NodePtr<'id, T>> {
+        // Step 1: detach the range from its neighbours.
+        let before: Option<NodePtr<'id, T>> = start
+            .borrow_mut(token)
+            .prev
+            .take()
+            .and_then(|p| p.upgrade());
+        let after: Option<NodePtr<'id, T>> = end.borrow_mut(token).next.take();
+
+        // Step 2: find the tail of the replacement.
+        let replacement_tail: Option<NodePtr<'id, T>> = replacement_head.as_ref().map(|head| {
+            let mut tail = Arc::clone(head);
+            while let Some(next) = tail.borrow(token).next().cloned() {
+                tail = next;
+            }
+            tail
+        });
+
+        // Step 3: link `before` to the replacement (or directly to `after`).
+        let new_start = replacement_head.or_else(|| after.clone());
+        if let Some(before) = &before {
+            *before.borrow_mut(token).next = new_start.clone();
+        }
+        if let Some(new_start) = &new_start {
+            new_start.borrow_mut(token).prev = before.as_ref().map(Arc::downgrade);
+        }
+
+        // Step 4: link the replacement (or `before`) to `after`.
+        let new_end = replacement_tail.or(before);
+        if let Some(after) = &after {
+            after.borrow_mut(token).prev = new_end.as_ref().map(Arc::downgrade);
+        }
+        if let Some(new_end) = &new_end {
+            *new_end.borrow_mut(token).next = after;
+        }
+
+        new_start
+    }
+
+    /// Cut the list after its first `at` nodes, returning the head of the detached rest
+    /// (or `None` if the list isn't longer than `at`).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `at` is 0: `node` itself always stays in its list.
+    pub fn split_off(
+        node: &NodePtr<'id, T>,
+        at: usize,
+        token: &mut GhostToken<'id>,
+    ) -> Option<NodePtr<'id, T>> {
+        assert!(at > 0, "can't split off the first node of a list");
+        let mut last = Arc::clone(node);
+        for _ in 1..at {
+            last = last.borrow(token).next().cloned()?;
+        }
+        let rest = last.borrow_mut(token).next.take()?;
+        rest.borrow_mut(token).prev = None;
+        Some(rest)
+    }
+
+    /// Move the last `n % len` nodes to the front, returning the new head. The nodes to move
+    /// are found by walking the `prev` links back from the tail.
+    pub fn rotate_right(
+        head: &NodePtr<'id, T>,
+        n: usize,
+        token: &mut GhostToken<'id>,
+    ) -> NodePtr<'id, T> {
+        let len = Node::count_from(head, token);
+        let n = n % len;
+        if n == 0 {
+            return Arc::clone(head);
+        }
+        let tail = Self::node_at(head, len - 1, token).unwrap();
+        let mut new_head = Arc::clone(&tail);
+        for _ in 1..n {
+            new_head = new_head.borrow(token).prev().unwrap();
+        }
+
+        // cut the list before `new_head` ...
+        let new_tail = new_head
+            .borrow_mut(token)
+            .prev
+            .take()
+            .and_then(|p| p.upgrade())
+            .unwrap();
+        *new_tail.borrow_mut(token).next = None;
+        // ... and link the old tail to the old head
+        head.borrow_mut(token).prev = Some(Arc::downgrade(&tail));
+        *tail.borrow_mut(token).next = Some(Arc::clone(head));
+        new_head
+    }
+
+    /// Stable O(n log n) merge sort. The nodes are relinked rather than having their
+    /// data moved, so existing pointers keep referring to the same elements.
+    /// Returns the new head.
+    pub fn merge_sort(node: &NodePtr<'id, T>, token: &mut GhostToken<'id>) -> NodePtr<'id, T>
+    where
+        T: Ord,
+    {
+        let len = Node::count_from(node, token);
+        match Self::split_off(node, len.div_ceil(2), token) {
+            None => Arc::clone(node),
+            Some(second_half) => {
+                let first = Self::merge_sort(node, token);
+                let second = Self::merge_sort(&second_half, token);
+                Self::merge_sorted(Some(first), Some(second), token).unwrap()
+            }
+        }
+    }
+
+    /// Merge two ascending lists into one ascending list by relinking their nodes,
+    /// returning the new head. On ties, elements of `a` go first.
+    pub fn merge_sorted(
+        a: Option<NodePtr<'id, T>>,
+        b: Option<NodePtr<'id, T>>,
+        token: &mut GhostToken<'id>,
+    ) -> Option<NodePtr<'id, T>>
+    where
+        T: Ord,
+    {
+        let (mut a, mut b) = (a, b);
+        let mut head: Option<NodePtr<'id, T>> = None;
+        let mut tail: Option<NodePtr<'id, T>> = None;
+        loop {
+            let source = match (&a, &b) {
+                (Some(x), Some(y)) if x.borrow(token).data <= y.borrow(token).data => &mut a,
+                (Some(_), Some(_)) | (None, Some(_)) => &mut b,
+                (Some(_), None) => &mut a,
+                (None, None) => break,
+            };
+            // detach the smallest node from its list ...
+            let node = source.take().unwrap();
+            *source = node.borrow_mut(token).next.take();
+
+            // ... and append it to the merged one
+            node.borrow_mut(token).prev = tail.as_ref().map(Arc::downgrade);
+            match &tail {
+                Some(tail) => *tail.borrow_mut(token).next = Some(Arc::clone(&node)),
+                None => head = Some(Arc::clone(&node)),
+            }
+            tail = Some(node);
+        }
+        head
+    }
+
+    /// Unlink every node whose key equals the key of the node before it, so only the
+    /// first node of each run is kept.
+    pub fn dedup_by_key<K: PartialEq>(
+        node: &NodePtr<'id, T>,
+        token: &mut GhostToken<'id>,
+        key: impl Fn(&T) -> K,
+    ) {
+        let mut cur = Arc::clone(node);
+        let mut cur_key = key(&cur.borrow(token).data);
+        while let Some(next) = cur.borrow(token).next().cloned() {
+            let next_key = key(&next.borrow(token).data);
+            if next_key == cur_key {
+                Self::remove(&next, token);
+            } else {
+                cur = next;
+                cur_key = next_key;
+            }
+        }
+    }
+
+    /// Reverse the list starting at `node` by relinking, returning the new head.
+    pub fn reverse(node: &NodePtr<'id, T>, token: &mut GhostToken<'id>) -> NodePtr<'id, T> {
+        let mut prev: Option<NodePtr<'id, T>> = None;
+        let mut cur: Option<NodePtr<'id, T>> = Some(node.clone());
+        while let Some(node) = cur {
+            let inner: &mut Node<'id, T> = node.borrow_mut(token);
+            let next = inner.next.take();
+            inner.prev = next.as_ref().map(Arc::downgrade);
+            *inner.next = prev;
+            prev = Some(node);
+            cur = next;
+        }
+        prev.unwrap()
+    }
+
+    /// Build a new list from the values in order, or `None` if there are none.
+    fn build(
+        token: &mut GhostToken<'id>,
+        values: impl IntoIterator<Item = T>,
+    ) -> Option<NodePtr<'id, T>> {
+        Node::new_chain(token, values).map(|(head, _tail)| head)
+    }
+
+    /// Build a detached chain of `elements` and return its head and tail, e.g. to splice
+    /// it into another list later. Returns `None` if there are no elements.
+    pub fn new_chain(
+        token: &mut GhostToken<'id>,
+        elements: impl IntoIterator<Item = T>,
+    ) -> Option<(NodePtr<'id, T>, NodePtr<'id, T>)> {
+        let mut elements = elements.into_iter();
+        let head = Node::new(elements.next()?);
+        let mut tail = Arc::clone(&head);
+        for value in elements {
+            let node = Node::new(value);
+            Node::insert_next(&tail, Arc::clone(&node), token);
+            tail = node;
+        }
+        Some((head, tail))
+    }
+
+    /// Build a list from `elements` in order, like `from_iter` of the other backends,
+    /// but with an explicit error instead of `None` when there are no elements.
+    pub fn from_iter(
+        token: &mut GhostToken<'id>,
+        elements: impl IntoIterator<Item = T>,
+    ) -> Result<NodePtr<'id, T>, EmptyListError> {
+        Node::build(token, elements).ok_or(EmptyListError)
+    }
+
+    /// Build a list from the deque's elements, front to back.
+    /// Returns `None` for an empty deque.
+    pub fn from_vecdeque(
+        token: &mut GhostToken<'id>,
+        deque: VecDeque<T>,
+    ) -> Option<NodePtr<'id, T>> {
+        Node::build(token, deque)
+    }
+
+    /// Build a new list (under the same brand) out of the values `f` maps each element to,
+    /// returning `None` if that yields nothing.
+    pub fn flat_map<U>(
+        node: &NodePtr<'id, T>,
+        token: &mut GhostToken<'id>,
+        f: impl Fn(&T) -> Vec<U>,
+    ) -> Option<NodePtr<'id, U>> {
+        // the token is borrowed immutably for reading, so collect before building
+        let values: Vec<U> = Node::iter(node, token).flat_map(f).collect();
+        Node::build(token, values)
+    }
+
+    /// Build a new list of the running accumulator values: `f(init, first)`,
+    /// then `f` of that and the second element, and so on.
+    pub fn scan<U: Clone>(
+        node: &NodePtr<'id, T>,
+        token: &mut GhostToken<'id>,
+        init: U,
+        f: impl Fn(&U, &T) -> U,
+    ) -> Option<NodePtr<'id, U>> {
+        let mut acc = init;
+        let mut values: Vec<U> = vec![];
+        for data in Node::iter(node, token) {
+            acc = f(&acc, data);
+            values.push(acc.clone());
+        }
+        Node::build(token, values)
+    }
+
+    /// The node `index` steps after `node`.
+    pub fn node_at(
+        node: &NodePtr<'id, T>,
+        index: usize,
+        token: &GhostToken<'id>,
+    ) -> Option<NodePtr<'id, T>> {
+        let mut cur = Arc::clone(node);
+        for _ in 0..index {
+            cur = cur.borrow(token).next().cloned()?;
+        }
+        Some(cur)
+    }
+
+    /// Apply `f` to the element at `index`, returning whether `index` was in range.
+    pub fn apply_at(
+        node: &NodePtr<'id, T>,
+        index: usize,
+        token: &mut GhostToken<'id>,
+        f: impl FnOnce(&mut T),
+    ) -> bool {
+        match Self::node_at(node, index, token) {
+            Some(target) => {
+                f(&mut target.borrow_mut(token).data);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Put `value` at `index`, returning the element it replaced,
+    /// or `None` (dropping `value`) if `index` is out of range.
+    pub fn replace_at(
+        node: &NodePtr<'id, T>,
+        index: usize,
+        value: T,
+        token: &mut GhostToken<'id>,
+    ) -> Option<T> {
+        let target = Self::node_at(node, index, token)?;
+        Some(mem::replace(&mut target.borrow_mut(token).data, value))
     }
 
     /// Construct an imutable iterator to traverse immutably.
@@ -90,6 +557,38 @@ impl<'id, T> Node<'id, T> {
         }
     }
 
+    /// Construct an iterator walking the `prev` links from `tail` to the head.
+    pub fn iter_rev<'iter>(
+        tail: &NodePtr<'id, T>,
+        token: &'iter GhostToken<'id>,
+    ) -> RevIter<'id, 'iter, T> {
+        RevIter {
+            cur: Some(Arc::clone(tail)),
+            token,
+        }
+    }
+
+    /// The node `n` steps before `tail` (0 is `tail` itself), found by walking `prev`.
+    /// Like `iter_rev` it returns the node rather than a reference to its data.
+    pub fn nth_back(
+        tail: &NodePtr<'id, T>,
+        n: usize,
+        token: &GhostToken<'id>,
+    ) -> Option<NodePtr<'id, T>> {
+        Node::iter_rev(tail, token).nth(n)
+    }
+
+    /// Fold from `tail` toward the head via the `prev` links, giving right-fold
+    /// semantics without reversing the list.
+    pub fn fold_rev<U>(
+        tail: &NodePtr<'id, T>,
+        token: &GhostToken<'id>,
+        init: U,
+        mut f: impl FnMut(U, &T) -> U,
+    ) -> U {
+        Node::iter_rev(tail, token).fold(init, |acc, node| f(acc, &node.borrow(token).data))
+    }
+
     /// Mutable iteration only works as "interior iteration", since we cannot hand out mutable references
     /// to multiple nodes at the same time.
     pub fn iter_mut(
@@ -105,6 +604,19 @@ impl<'id, T> Node<'id, T> {
         }
     }
 
+    /// Like `iter_mut`, but also passes the position of each element to `f`.
+    pub fn enumerate_mut(
+        node: &NodePtr<'id, T>,
+        token: &mut GhostToken<'id>,
+        mut f: impl FnMut(usize, &mut T),
+    ) {
+        let mut index = 0;
+        Node::iter_mut(node, token, |data| {
+            f(index, data);
+            index += 1;
+        });
+    }
+
     /// Immutable interior traversal.
     pub fn iterate(node: &NodePtr<'id, T>, token: &GhostToken<'id>, f: impl Fn(&T)) {
         let mut cur: Option<&GhostCell<'id, Node<'id, T>>> = Some(node.as_ref());
@@ -118,8 +630,407 @@ impl<'id, T> Node<'id, T> {
     pub fn view_as_vec<'a>(node: &'a NodePtr<'id, T>, token: &'a GhostToken<'id>) -> Vec<&'a T> {
         Node::iter(node, token).collect::<Vec<_>>()
     }
+
+    /// Like `view_as_vec`, but stops after `max` elements, so it also terminates on a
+    /// list whose `next` links were corrupted into a cycle.
+    pub fn view_as_vec_limited<'a>(
+        node: &'a NodePtr<'id, T>,
+        token: &'a GhostToken<'id>,
+        max: usize,
+    ) -> Vec<&'a T> {
+        Node::iter(node, token).take(max).collect()
+    }
+
+    /// Like `view_as_vec`, but appends to a caller-provided buffer so it can be reused.
+    pub fn collect_into<'a>(
+        node: &'a NodePtr<'id, T>,
+        token: &'a GhostToken<'id>,
+        out: &mut Vec<&'a T>,
+    ) {
+        out.extend(Node::iter(node, token));
+    }
+
+    /// Immutable iteration that also yields the position of each element.
+    pub fn iter_with_index<'iter>(
+        node: &'iter NodePtr<'id, T>,
+        token: &'iter GhostToken<'id>,
+    ) -> Enumerate<Iter<'id, 'iter, T>> {
+        Node::iter(node, token).enumerate()
+    }
+
+    /// Find the node with the greatest key. On ties the first such node is returned.
+    pub fn max_by_key<K: Ord>(
+        node: &NodePtr<'id, T>,
+        token: &GhostToken<'id>,
+        key: impl Fn(&T) -> K,
+    ) -> Option<NodePtr<'id, T>> {
+        Self::find_by_key(node, token, key, |candidate, best| candidate > best)
+    }
+
+    /// Find the node with the smallest key. On ties the first such node is returned.
+    pub fn min_by_key<K: Ord>(
+        node: &NodePtr<'id, T>,
+        token: &GhostToken<'id>,
+        key: impl Fn(&T) -> K,
+    ) -> Option<NodePtr<'id, T>> {
+        Self::find_by_key(node, token, key, |candidate, best| candidate < best)
+    }
+
+    /// Fold in list order, stopping as soon as `f` returns `ControlFlow::Break`.
+    pub fn fold_while<U>(
+        node: &NodePtr<'id, T>,
+        token: &GhostToken<'id>,
+        init: U,
+        mut f: impl FnMut(U, &T) -> ControlFlow<U, U>,
+    ) -> U {
+        let mut acc = init;
+        for data in Node::iter(node, token) {
+            match f(acc, data) {
+                ControlFlow::Continue(next) => acc = next,
+                ControlFlow::Break(result) => return result,
+            }
+        }
+        acc
+    }
+
+    /// Whether `pred` holds for some element, stopping at the first one that matches.
+    pub fn any(
+        node: &NodePtr<'id, T>,
+        token: &GhostToken<'id>,
+        pred: impl FnMut(&T) -> bool,
+    ) -> bool {
+        Node::iter(node, token).any(pred)
+    }
+
+    /// Whether `pred` holds for every element, stopping at the first one that doesn't match.
+    pub fn all(
+        node: &NodePtr<'id, T>,
+        token: &GhostToken<'id>,
+        pred: impl FnMut(&T) -> bool,
+    ) -> bool {
+        Node::iter(node, token).all(pred)
+    }
+
+    /// Call `f` on every pair of adjacent elements, in list order.
+    pub fn for_each_pair(
+        node: &NodePtr<'id, T>,
+        token: &GhostToken<'id>,
+        mut f: impl FnMut(&T, &T),
+    ) {
+        let mut iter = Node::iter(node, token);
+        if let Some(mut prev) = iter.next() {
+            for cur in iter {
+                f(prev, cur);
+                prev = cur;
+            }
+        }
+    }
+
+    /// Search an ascending list for `value`. Returns `Ok(index)` if it is found and
+    /// `Err(index)` with the position it could be inserted at otherwise.
+    ///
+    /// Unlike slice `binary_search` this is O(n): a list can't be indexed, so the
+    /// elements are scanned linearly, stopping at the first one that isn't smaller.
+    pub fn binary_search(
+        node: &NodePtr<'id, T>,
+        token: &GhostToken<'id>,
+        value: &T,
+    ) -> Result<usize, usize>
+    where
+        T: Ord,
+    {
+        let mut index = 0;
+        for data in Node::iter(node, token) {
+            match data.cmp(value) {
+                Ordering::Less => index += 1,
+                Ordering::Equal => return Ok(index),
+                Ordering::Greater => return Err(index),
+            }
+        }
+        Err(index)
+    }
+
+    /// The index of the first element for which `pred` is false, assuming the list is
+    /// partitioned by `pred` (all `true` elements come first). Like `binary_search` this
+    /// is a linear scan rather than the bisection of slice `partition_point`.
+    pub fn partition_point(
+        node: &NodePtr<'id, T>,
+        token: &GhostToken<'id>,
+        pred: impl Fn(&T) -> bool,
+    ) -> usize {
+        Node::iter(node, token).take_while(|x| pred(x)).count()
+    }
+
+    pub fn to_vecdeque(node: &NodePtr<'id, T>, token: &GhostToken<'id>) -> VecDeque<T>
+    where
+        T: Clone,
+    {
+        Node::iter(node, token).cloned().collect()
+    }
+
+    /// Clone the elements into an owned slice that can be handed to code without the token.
+    pub fn snapshot(node: &NodePtr<'id, T>, token: &GhostToken<'id>) -> Box<[T]>
+    where
+        T: Clone,
+    {
+        Node::iter(node, token).cloned().collect()
+    }
+
+    /// Group the elements into chunks of `size` references; the last chunk may be smaller.
+    /// A `size` of 0 yields no chunks at all.
+    pub fn chunk_view<'a>(
+        node: &'a NodePtr<'id, T>,
+        token: &'a GhostToken<'id>,
+        size: usize,
+    ) -> Vec<Vec<&'a T>> {
+        if size == 0 {
+            return vec![];
+        }
+        let mut chunks: Vec<Vec<&'a T>> = vec![];
+        for data in Node::iter(node, token) {
+            match chunks.last_mut() {
+                Some(chunk) if chunk.len() < size => chunk.push(data),
+                _ => chunks.push(vec![data]),
+            }
+        }
+        chunks
+    }
+
+    /// References to the elements at indices 0, `step`, 2 * `step`, ...
+    ///
+    /// # Panics
+    ///
+    /// Panics if `step` is 0, like `Iterator::step_by`.
+    pub fn step_by_view<'a>(
+        node: &'a NodePtr<'id, T>,
+        token: &'a GhostToken<'id>,
+        step: usize,
+    ) -> Vec<&'a T> {
+        Node::iter(node, token).step_by(step).collect()
+    }
+
+    /// References to the leading elements for which `pred` holds.
+    pub fn take_while_view<'a>(
+        node: &'a NodePtr<'id, T>,
+        token: &'a GhostToken<'id>,
+        pred: impl Fn(&T) -> bool,
+    ) -> Vec<&'a T> {
+        Node::iter(node, token).take_while(|x| pred(x)).collect()
+    }
+
+    /// References to the elements from the first one for which `pred` doesn't hold.
+    pub fn skip_while_view<'a>(
+        node: &'a NodePtr<'id, T>,
+        token: &'a GhostToken<'id>,
+        pred: impl Fn(&T) -> bool,
+    ) -> Vec<&'a T> {
+        Node::iter(node, token).skip_while(|x| pred(x)).collect()
+    }
+
+    /// Overlapping windows of `size` consecutive element references, like slice `windows`.
+    /// A `size` of 0 or one larger than the list yields no windows.
+    pub fn windows_view<'a>(
+        node: &'a NodePtr<'id, T>,
+        token: &'a GhostToken<'id>,
+        size: usize,
+    ) -> Vec<Vec<&'a T>> {
+        if size == 0 {
+            return vec![];
+        }
+        Node::view_as_vec(node, token)
+            .windows(size)
+            .map(|window| window.to_vec())
+            .collect()
+    }
+
+    /// The address of each node in list order, for checking node identities.
+    pub fn as_ptr_vec(node: &NodePtr<'id, T>, token: &GhostToken<'id>) -> Vec<*const ()> {
+        let mut ptrs = vec![];
+        let mut cur: Option<&NodePtr<'id, T>> = Some(node);
+        while let Some(node) = cur {
+            ptrs.push(Arc::as_ptr(node) as *const ());
+            cur = node.borrow(token).next.as_ref();
+        }
+        ptrs
+    }
+
+    /// Whether the elements are in non-decreasing order.
+    pub fn is_sorted(node: &NodePtr<'id, T>, token: &GhostToken<'id>) -> bool
+    where
+        T: PartialOrd,
+    {
+        Node::iter(node, token).is_sorted()
+    }
+
+    /// Index of the first element equal to `value`.
+    pub fn position(node: &NodePtr<'id, T>, token: &GhostToken<'id>, value: &T) -> Option<usize>
+    where
+        T: PartialEq,
+    {
+        Node::iter(node, token).position(|data| data == value)
+    }
+
+    pub fn contains(node: &NodePtr<'id, T>, token: &GhostToken<'id>, value: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        Node::position(node, token, value).is_some()
+    }
+
+    /// Index (counted from the head) of the last element equal to `value`, found by
+    /// walking the `prev` links backward from `tail`. The walk continues to the head
+    /// to learn the list length, so this is always O(n).
+    pub fn rposition(tail: &NodePtr<'id, T>, token: &GhostToken<'id>, value: &T) -> Option<usize>
+    where
+        T: PartialEq,
+    {
+        let mut steps = 0;
+        let mut found_at_back: Option<usize> = None;
+        let mut cur: Option<NodePtr<'id, T>> = Some(Arc::clone(tail));
+        while let Some(node) = cur {
+            let inner: &Node<'id, T> = node.borrow(token);
+            if found_at_back.is_none() && inner.data == *value {
+                found_at_back = Some(steps);
+            }
+            steps += 1;
+            cur = inner.prev();
+        }
+        found_at_back.map(|back| steps - 1 - back)
+    }
+
+    /// Number of nodes from `node` (inclusive) to the end of the list.
+    pub fn count_from(node: &NodePtr<'id, T>, token: &GhostToken<'id>) -> usize {
+        Node::iter(node, token).count()
+    }
+
+    /// The length of the cycle the `next` links run into, or `None` if they reach an end.
+    /// A well-formed list never has one; this is for diagnosing corrupted links.
+    pub fn detect_cycle(head: &NodePtr<'id, T>, token: &GhostToken<'id>) -> Option<usize> {
+        let step = |node: &NodePtr<'id, T>| node.borrow(token).next().cloned();
+        // Floyd's tortoise and hare: the hare moves two steps for every step of the
+        // tortoise, so it can only catch up with it inside a cycle
+        let mut tortoise = Arc::clone(head);
+        let mut hare = Arc::clone(head);
+        loop {
+            hare = step(&step(&hare)?)?;
+            tortoise = step(&tortoise).unwrap();
+            if Self::same(&tortoise, &hare) {
+                break;
+            }
+        }
+        let mut len = 1;
+        let mut cur = step(&tortoise).unwrap();
+        while !Self::same(&cur, &tortoise) {
+            cur = step(&cur).unwrap();
+            len += 1;
+        }
+        Some(len)
+    }
+
+    /// How many `next` steps lead from `a` to `b`, or `None` if `b` can't be reached from `a`.
+    pub fn distance(
+        a: &NodePtr<'id, T>,
+        b: &NodePtr<'id, T>,
+        token: &GhostToken<'id>,
+    ) -> Option<usize> {
+        let mut steps = 0;
+        let mut cur: Option<&NodePtr<'id, T>> = Some(a);
+        while let Some(node) = cur {
+            if Self::same(node, b) {
+                return Some(steps);
+            }
+            steps += 1;
+            cur = node.borrow(token).next.as_ref();
+        }
+        None
+    }
+
+    /// Whether `target` is one of the nodes of the list starting at `head` (by identity),
+    /// e.g. to check a node before handing it to `splice` or `remove`.
+    pub fn contains_node(
+        head: &NodePtr<'id, T>,
+        target: &NodePtr<'id, T>,
+        token: &GhostToken<'id>,
+    ) -> bool {
+        Self::distance(head, target, token).is_some()
+    }
+
+    /// Group consecutive elements that share a key.
+    pub fn group_by<'a, K: PartialEq>(
+        node: &'a NodePtr<'id, T>,
+        token: &'a GhostToken<'id>,
+        key: impl Fn(&T) -> K,
+    ) -> Vec<Vec<&'a T>> {
+        let mut groups: Vec<(K, Vec<&'a T>)> = vec![];
+        for data in Node::iter(node, token) {
+            let k = key(data);
+            match groups.last_mut() {
+                Some((group_key, group)) if *group_key == k => group.push(data),
+                _ => groups.push((k, vec![data])),
+            }
+        }
+        groups.into_iter().map(|(_, group)| group).collect()
+    }
+
+    /// `Arc::strong_count` of each node in list order. Every node but the head is
+    /// referenced by its predecessor, so anything above that comes from outside the list.
+    pub fn strong_counts(node: &NodePtr<'id, T>, token: &GhostToken<'id>) -> Vec<usize> {
+        let mut counts = vec![];
+        let mut cur: Option<&NodePtr<'id, T>> = Some(node);
+        while let Some(node) = cur {
+            counts.push(Arc::strong_count(node));
+            cur = node.borrow(token).next.as_ref();
+        }
+        counts
+    }
+
+    /// Whether any node is referenced from outside the list, i.e. has a strong count
+    /// above the one from its predecessor (for the head: from the caller's `head`).
+    /// Such nodes can't give up their data, so `remove_value`, `into_vec` and the pops
+    /// of `ListWrapper` would skip their elements.
+    pub fn has_shared_nodes(head: &NodePtr<'id, T>, token: &GhostToken<'id>) -> bool {
+        Node::strong_counts(head, token)
+            .into_iter()
+            .any(|count| count > 1)
+    }
+
+    fn find_by_key<K>(
+        node: &NodePtr<'id, T>,
+        token: &GhostToken<'id>,
+        key: impl Fn(&T) -> K,
+        replaces: impl Fn(&K, &K) -> bool,
+    ) -> Option<NodePtr<'id, T>> {
+        let mut best: Option<(&NodePtr<'id, T>, K)> = None;
+        let mut cur: Option<&NodePtr<'id, T>> = Some(node);
+        while let Some(node) = cur {
+            let inner: &Node<'id, T> = node.borrow(token);
+            let k = key(&inner.data);
+            let is_better = match &best {
+                Some((_, best_key)) => replaces(&k, best_key),
+                None => true,
+            };
+            if is_better {
+                best = Some((node, k));
+            }
+            cur = inner.next.as_ref();
+        }
+        best.map(|(node, _)| Arc::clone(node))
+    }
+}
+
+/// The error of `Node::from_iter`: a list has at least one node, so it can't be built
+/// from no elements.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EmptyListError;
+
+impl core::fmt::Display for EmptyListError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("can't build a list from no elements")
+    }
 }
 
+impl core::error::Error for EmptyListError {}
+
 /// An immutable iterator.
 pub struct Iter<'id, 'iter, T> {
     cur: Option<&'iter GhostCell<'id, Node<'id, T>>>,
@@ -144,7 +1055,150 @@ where
     }
 }
 
-fn init_list<'id>(
+/// Once `cur` is `None` it stays `None`, so the iterator is fused.
+impl<'id, 'iter, T> FusedIterator for Iter<'id, 'iter, T> where T: 'iter {}
+
+/// An iterator over the `prev` links.
+///
+/// Unlike `Iter` it yields the nodes themselves: the back-links are `Weak`, so a
+/// previous node isn't borrowed from anything that is guaranteed to live for `'iter`.
+pub struct RevIter<'id, 'iter, T> {
+    cur: Option<NodePtr<'id, T>>,
+    token: &'iter GhostToken<'id>,
+}
+
+impl<'id, 'iter, T> Iterator for RevIter<'id, 'iter, T> {
+    type Item = NodePtr<'id, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.cur.take()?;
+        self.cur = node.borrow(self.token).prev();
+        Some(node)
+    }
+}
+
+/// A node of a `NodeArena`, linked to its neighbours by index.
+struct ArenaNode<T> {
+    data: T,
+    prev: Option<usize>,
+    next: Option<usize>,
+}
+
+/// An alternative to `NodePtr` lists for large lists: nodes are bump-allocated in one
+/// `Vec` and referred to by index instead of each living in its own `Arc`.
+///
+/// Every node is still a `GhostCell`, so reading and relinking go through the token just
+/// like for `Node`. Removed nodes are only unlinked; their slots are freed together with
+/// the arena.
+pub struct NodeArena<'id, T> {
+    nodes: Vec<GhostCell<'id, ArenaNode<T>>>,
+}
+
+impl<'id, T> NodeArena<'id, T> {
+    pub fn new() -> Self {
+        Self { nodes: Vec::new() }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            nodes: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Allocate an unlinked node holding `value` and return its index.
+    pub fn alloc(&mut self, value: T) -> usize {
+        self.nodes.push(GhostCell::new(ArenaNode {
+            data: value,
+            prev: None,
+            next: None,
+        }));
+        self.nodes.len() - 1
+    }
+
+    pub fn get<'a>(&'a self, node: usize, token: &'a GhostToken<'id>) -> &'a T {
+        &self.nodes[node].borrow(token).data
+    }
+
+    pub fn get_mut<'a>(&'a self, node: usize, token: &'a mut GhostToken<'id>) -> &'a mut T {
+        &mut self.nodes[node].borrow_mut(token).data
+    }
+
+    pub fn next(&self, node: usize, token: &GhostToken<'id>) -> Option<usize> {
+        self.nodes[node].borrow(token).next
+    }
+
+    pub fn prev(&self, node: usize, token: &GhostToken<'id>) -> Option<usize> {
+        self.nodes[node].borrow(token).prev
+    }
+
+    /// Unlink the nodes adjacent to `node`, like `Node::remove`.
+    pub fn remove(&self, node: usize, token: &mut GhostToken<'id>) {
+        let inner = self.nodes[node].borrow_mut(token);
+        let (old_prev, old_next) = (inner.prev.take(), inner.next.take());
+        if let Some(old_next) = old_next {
+            self.nodes[old_next].borrow_mut(token).prev = old_prev;
+        }
+        if let Some(old_prev) = old_prev {
+            self.nodes[old_prev].borrow_mut(token).next = old_next;
+        }
+    }
+
+    /// Insert `node2` right after `node1`, like `Node::insert_next`.
+    /// Inserting a node after itself is a no-op.
+    pub fn insert_next(&self, node1: usize, node2: usize, token: &mut GhostToken<'id>) {
+        if node1 == node2 {
+            return;
+        }
+        self.remove(node2, token);
+
+        let node1_old_next = self.nodes[node1].borrow_mut(token).next.take();
+        if let Some(node1_old_next) = node1_old_next {
+            self.nodes[node1_old_next].borrow_mut(token).prev = Some(node2);
+        }
+
+        let node2_inner = self.nodes[node2].borrow_mut(token);
+        node2_inner.prev = Some(node1);
+        node2_inner.next = node1_old_next;
+
+        self.nodes[node1].borrow_mut(token).next = Some(node2);
+    }
+
+    /// Allocate a linked list of `values` and return the index of its head.
+    pub fn from_iter(
+        &mut self,
+        token: &mut GhostToken<'id>,
+        values: impl IntoIterator<Item = T>,
+    ) -> Option<usize> {
+        let mut values = values.into_iter();
+        let head = self.alloc(values.next()?);
+        let mut tail = head;
+        for value in values {
+            let node = self.alloc(value);
+            self.insert_next(tail, node, token);
+            tail = node;
+        }
+        Some(head)
+    }
+
+    pub fn view_as_vec<'a>(&'a self, node: usize, token: &'a GhostToken<'id>) -> Vec<&'a T> {
+        let mut v = vec![];
+        let mut cur = Some(node);
+        while let Some(node) = cur {
+            let inner = self.nodes[node].borrow(token);
+            v.push(&inner.data);
+            cur = inner.next;
+        }
+        v
+    }
+}
+
+impl<'id, T> Default for NodeArena<'id, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub fn init_list<'id>(
     token: &mut GhostToken<'id>,
     list_size: i32,
 ) -> (NodePtr<'id, i32>, NodePtr<'id, i32>) {
@@ -161,223 +1215,409 @@ fn init_list<'id>(
     (head, tail)
 }
 
-fn print_list<'id, T: std::fmt::Debug>(list: &NodePtr<'id, T>, token: &GhostToken<'id>) {
-    println!(
-        "{}",
-        Node::iter(list, token)
-            .map(|n| format!("{:?}", n))
-            .collect::<Vec<_>>()
-            .join(", ")
-    );
+/// Build a list like `init_list` whose tail links back to the node at `loop_to`, and run `f`
+/// on its head and tail. The cycle is broken again before returning, so the nodes are freed.
+///
+/// Such a list can't be made through the safe API; this is for trying out `detect_cycle` and
+/// `view_as_vec_limited`.
+pub fn with_cyclic_list<'id, R>(
+    token: &mut GhostToken<'id>,
+    list_size: i32,
+    loop_to: usize,
+    f: impl FnOnce(&NodePtr<'id, i32>, &NodePtr<'id, i32>, &GhostToken<'id>) -> R,
+) -> R {
+    let (head, tail) = init_list(token, list_size);
+    let target = Node::node_at(&head, loop_to, token).expect("loop_to is past the end of the list");
+    tail.borrow_mut(token).overwrite_next(Some(target));
+    let result = f(&head, &tail, token);
+    tail.borrow_mut(token).overwrite_next(None);
+    result
 }
 
-struct ListWrapper<'id, T> {
-    head: NodePtr<'id, T>,
+pub struct ListWrapper<'id, T> {
+    head: Option<NodePtr<'id, T>>,
+    tail: Option<NodePtr<'id, T>>,
+    len: usize,
     token: GhostToken<'id>,
 }
 
 impl<'id, T> ListWrapper<'id, T> {
     pub fn new(head: NodePtr<'id, T>, token: GhostToken<'id>) -> Self {
-        Self { head, token }
+        let mut tail = Arc::clone(&head);
+        let mut len = 1;
+        while let Some(next) = tail.borrow(&token).next().cloned() {
+            tail = next;
+            len += 1;
+        }
+        Self {
+            head: Some(head),
+            tail: Some(tail),
+            len,
+            token,
+        }
+    }
+
+    /// A wrapper without any elements, to be filled with `push_back`.
+    pub fn empty(token: GhostToken<'id>) -> Self {
+        Self {
+            head: None,
+            tail: None,
+            len: 0,
+            token,
+        }
     }
 
     pub fn create<I: IntoIterator<Item = T>>(token: GhostToken<'id>, elements: I) -> Self {
-        let mut iter = elements.into_iter();
-        let head = Node::new(iter.next().unwrap());
-        let mut list = ListWrapper { head, token };
-        let mut tail = Arc::clone(&list.head);
-        while let Some(e) = iter.next() {
-            let node = Node::new(e);
-            Node::insert_next(&tail, Arc::clone(&node), &mut list.token);
-            tail = node;
+        let mut list = ListWrapper::empty(token);
+        for e in elements {
+            list.push_back(e);
         }
         list
     }
 
-    pub fn iter<'a>(&'a self) -> Iter<'id, 'a, T> {
-        Iter {
-            cur: Some(&self.head),
-            token: &self.token,
+    pub fn push_front(&mut self, value: T) {
+        let node = Node::new(value);
+        match self.head.take() {
+            None => self.tail = Some(Arc::clone(&node)),
+            Some(head) => Node::insert_prev(&head, Arc::clone(&node), &mut self.token),
         }
+        self.head = Some(node);
+        self.len += 1;
     }
 
-    pub fn expose_node(&self) -> NodePtr<'id, T> {
-        Arc::clone(&self.head)
+    pub fn push_back(&mut self, value: T) {
+        let node = Node::new(value);
+        match self.tail.take() {
+            None => self.head = Some(Arc::clone(&node)),
+            Some(tail) => Node::insert_next(&tail, Arc::clone(&node), &mut self.token),
+        }
+        self.tail = Some(node);
+        self.len += 1;
     }
 
-    pub fn expose_token(&self) -> &GhostToken<'id> {
-        &self.token
+    /// Remove the first element. If its node was aliased through `expose_node`,
+    /// it is still removed but `None` is returned (see `Node::remove_value`).
+    pub fn pop_front(&mut self) -> Option<T> {
+        let head = self.head.take()?;
+        self.head = head.borrow(&self.token).next().cloned();
+        if self.head.is_none() {
+            self.tail = None;
+        }
+        self.len -= 1;
+        Node::remove_value(head, &mut self.token)
     }
 
-    pub fn expose_mut_node(&mut self) -> &mut Node<'id, T> {
-        self.head.borrow_mut(&mut self.token)
+    /// Remove the last element, with the same aliasing caveat as `pop_front`.
+    pub fn pop_back(&mut self) -> Option<T> {
+        let tail = self.tail.take()?;
+        self.tail = tail.borrow(&self.token).prev();
+        if self.tail.is_none() {
+            self.head = None;
+        }
+        self.len -= 1;
+        Node::remove_value(tail, &mut self.token)
     }
-}
 
-impl<'id, T: Debug> Debug for ListWrapper<'id, T> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let view = Node::view_as_vec(&self.head, &self.token);
-        f.write_str(format!("{:?}", view).as_str())
+    /// Insert `value` so that it ends up at position `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index > len`.
+    pub fn insert_at(&mut self, index: usize, value: T) {
+        assert!(
+            index <= self.len,
+            "insertion index {} is out of range",
+            index
+        );
+        if index == self.len {
+            return self.push_back(value);
+        }
+        let node = Node::new(value);
+        if index == 0 {
+            let head = self.head.take().unwrap();
+            Node::insert_prev(&head, Arc::clone(&node), &mut self.token);
+            self.head = Some(node);
+        } else {
+            let prev = self.node_at(index - 1).unwrap();
+            Node::insert_next(&prev, node, &mut self.token);
+        }
+        self.len += 1;
     }
-}
 
-mod ownership {
-    pub mod data_structure_lib {
-        use std::sync::Arc;
-
-        use ghost_cell::{GhostCell, GhostToken};
-
-        // private struct, shouldn't be exposed to users
-        struct Rep {
-            a: i32,
+    /// Remove the element at `index`, with the same aliasing caveat as `pop_front`.
+    /// Returns `None` if `index` is out of range.
+    pub fn remove_at(&mut self, index: usize) -> Option<T> {
+        if index >= self.len {
+            return None;
         }
-        type RepPointer<'id> = Arc<GhostCell<'id, Rep>>;
-
-        pub struct S1<'id> {
-            data: RepPointer<'id>,
+        if index == 0 {
+            return self.pop_front();
         }
+        if index == self.len - 1 {
+            return self.pop_back();
+        }
+        let node = self.node_at(index)?;
+        self.len -= 1;
+        Node::remove_value(node, &mut self.token)
+    }
 
-        impl<'id> S1<'id> {
-            pub fn new(a: i32) -> Self {
-                Self {
-                    data: Arc::new(GhostCell::new(Rep { a })),
-                }
-            }
+    /// Remove the elements from `index` on and return them in order. A second wrapper
+    /// would need a token of its own, so the split-off part is returned by value instead.
+    /// Elements whose nodes are aliased are removed but left out, as in `pop_back`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `at > len`.
+    pub fn split_off(&mut self, at: usize) -> Vec<T> {
+        assert!(at <= self.len, "split index {} is out of range", at);
+        let mut rest: Vec<T> = (at..self.len).filter_map(|_| self.pop_back()).collect();
+        rest.reverse();
+        rest
+    }
 
-            /// mixing self' and other's representations is allowed when they
-            /// have common brand
-            pub fn mix_representations(&mut self, other: &S1<'id>) {
-                let other_rep = Arc::clone(&other.data);
-                self.data = other_rep;
+    /// Unlink every element that fails `pred`.
+    pub fn retain(&mut self, pred: impl Fn(&T) -> bool) {
+        let mut cur = self.head.take();
+        self.tail = None;
+        while let Some(node) = cur {
+            cur = node.borrow(&self.token).next().cloned();
+            if pred(&node.borrow(&self.token).data) {
+                self.head.get_or_insert_with(|| Arc::clone(&node));
+                self.tail = Some(node);
+            } else {
+                Node::remove(&node, &mut self.token);
+                self.len -= 1;
             }
         }
+    }
 
-        pub struct SWithToken<'id> {
-            token: GhostToken<'id>,
-            data: RepPointer<'id>,
+    /// Reverse the list in place with `Node::reverse`; the old head becomes the tail.
+    pub fn reverse(&mut self) {
+        if let Some(head) = self.head.take() {
+            self.head = Some(Node::reverse(&head, &mut self.token));
+            self.tail = Some(head);
         }
+    }
 
-        impl<'id> SWithToken<'id> {
-            pub fn new(a: i32, token: GhostToken<'id>) -> Self {
-                Self {
-                    token,
-                    data: Arc::new(GhostCell::new(Rep { a })),
-                }
+    /// Sort the list ascending with `Node::merge_sort`.
+    pub fn sort(&mut self)
+    where
+        T: Ord,
+    {
+        if let Some(head) = self.head.take() {
+            let head = Node::merge_sort(&head, &mut self.token);
+            let mut tail = Arc::clone(&head);
+            while let Some(next) = tail.borrow(&self.token).next().cloned() {
+                tail = next;
             }
+            self.head = Some(head);
+            self.tail = Some(tail);
+        }
+    }
 
-            // Does not compile, lifetimes don't match
-            pub fn mix_representations_fails<'id2>(&mut self, other: &SWithToken<'id2>) {
-                // let other_rep = Arc::clone(&other.data);
-                // self.data = other_rep;
-            }
+    /// Relink a node of this list to the front.
+    fn move_to_front(&mut self, node: &NodePtr<'id, T>) {
+        let head = self.head.take().unwrap();
+        if Node::same(&head, node) {
+            self.head = Some(head);
+            return;
+        }
+        if self
+            .tail
+            .as_ref()
+            .is_some_and(|tail| Node::same(tail, node))
+        {
+            self.tail = node.borrow(&self.token).prev();
         }
+        Node::insert_prev(&head, Arc::clone(node), &mut self.token);
+        self.head = Some(Arc::clone(node));
+    }
+
+    fn node_at(&self, index: usize) -> Option<NodePtr<'id, T>> {
+        Node::node_at(self.head.as_ref()?, index, &self.token)
+    }
+
+    pub fn clear(&mut self) {
+        self.head = None;
+        self.tail = None;
+        self.len = 0;
     }
-    pub mod client_lib {
-        use ghost_cell::GhostToken;
 
-        use crate::{init_list, ListWrapper};
+    pub fn len(&self) -> usize {
+        self.len
+    }
 
-        use super::data_structure_lib::*;
+    pub fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
 
-        pub fn mix_representations() {
-            let mut s1_1 = S1::new(1);
-            let s1_2 = S1::new(2);
+    pub fn iter<'a>(&'a self) -> Iter<'id, 'a, T> {
+        Iter {
+            cur: self.head.as_deref(),
+            token: &self.token,
+        }
+    }
 
-            s1_1.mix_representations(&s1_2);
+    /// Mutate every element with the stored token, like `Node::iter_mut`.
+    pub fn for_each_mut(&mut self, f: impl FnMut(&mut T)) {
+        if let Some(head) = &self.head {
+            Node::iter_mut(head, &mut self.token, f);
         }
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.iter().nth(index)
+    }
 
-        pub fn try_put_two_structs_in_one_vector() {
-            GhostToken::new(|mut token1| {
-                GhostToken::new(|mut token2| {
-                    let swt1 = SWithToken::new(1, token1);
-                    let swt2 = SWithToken::new(2, token2);
+    pub fn contains(&self, value: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        self.head
+            .as_ref()
+            .is_some_and(|head| Node::contains(head, &self.token, value))
+    }
 
-                    let mut swts1 = vec![swt1];
-                    let mut swts2 = vec![swt2];
+    pub fn position(&self, value: &T) -> Option<usize>
+    where
+        T: PartialEq,
+    {
+        self.head
+            .as_ref()
+            .and_then(|head| Node::position(head, &self.token, value))
+    }
 
-                    // does not compile as swt lists have different types (lifetimes in particular):
-                    // swts1.append(&mut swts2);
-                })
-            })
-        }
+    /// Apply `f` to every element. The result is a plain `Vec`, since a new wrapper would
+    /// need a token (and so a brand) of its own.
+    pub fn map_collect<U>(&self, f: impl Fn(&T) -> U) -> Vec<U> {
+        self.iter().map(f).collect()
+    }
 
-        pub fn mix_representations_fails() {
-            GhostToken::new(|mut token1| {
-                GhostToken::new(|mut token2| {
-                    let mut swt1 = SWithToken::new(1, token1);
-                    let swt2 = SWithToken::new(2, token2);
+    pub fn expose_node(&self) -> Option<NodePtr<'id, T>> {
+        self.head.clone()
+    }
 
-                    swt1.mix_representations_fails(&swt2);
-                })
-            })
-        }
+    pub fn expose_tail(&self) -> Option<NodePtr<'id, T>> {
+        self.tail.clone()
+    }
 
-        pub fn immutable_incoming_aliases_allowed() {
-            GhostToken::new(|mut token| {
-                let (list, _tail) = init_list(&mut token, 5);
-                let list_wrapper = ListWrapper::new(list, token);
+    /// Give up the wrapper, returning its head and the token.
+    pub fn into_parts(self) -> (Option<NodePtr<'id, T>>, GhostToken<'id>) {
+        (self.head, self.token)
+    }
 
-                let token_alias = list_wrapper.expose_token();
-                let node_alias = list_wrapper.expose_node();
-                let _x = node_alias.borrow(token_alias).data;
-            });
-        }
+    pub fn expose_token(&self) -> &GhostToken<'id> {
+        &self.token
+    }
 
-        pub fn mutable_incoming_alias_allowed() {
-            GhostToken::new(|mut token| {
-                let (list, _tail) = init_list(&mut token, 5);
-                let mut list_wrapper = ListWrapper::new(list, token);
+    pub fn expose_mut_node(&mut self) -> Option<&mut Node<'id, T>> {
+        let token = &mut self.token;
+        self.head.as_ref().map(|head| head.borrow_mut(token))
+    }
+}
 
-                let mut_node_ref = list_wrapper.expose_mut_node();
-                mut_node_ref.data = 666;
-                println!("{:?}", list_wrapper);
-            });
+impl<'id, T: Debug> Debug for ListWrapper<'id, T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// A least-recently-used cache on top of `ListWrapper`: the most recently used entry is
+/// kept at the front and the entry at the back is evicted once `capacity` is exceeded.
+pub struct LruList<'id, K, V> {
+    list: ListWrapper<'id, (K, V)>,
+    capacity: usize,
+}
+
+impl<'id, K: PartialEq, V> LruList<'id, K, V> {
+    pub fn new(token: GhostToken<'id>, capacity: usize) -> Self {
+        assert!(
+            capacity > 0,
+            "an LRU list needs room for at least one entry"
+        );
+        Self {
+            list: ListWrapper::empty(token),
+            capacity,
         }
+    }
+
+    /// Look up `key`, marking it as the most recently used entry.
+    pub fn access(&mut self, key: &K) -> Option<&V> {
+        let node = self.find(key)?;
+        self.list.move_to_front(&node);
+        self.list.iter().next().map(|(_, value)| value)
+    }
 
-        pub fn run_all_examples() {
-            mix_representations();
-            mix_representations_fails();
-            try_put_two_structs_in_one_vector();
-            immutable_incoming_aliases_allowed();
-            mutable_incoming_alias_allowed();
+    /// Insert or update `key` as the most recently used entry, returning the entry
+    /// evicted to stay within capacity, if any.
+    pub fn put(&mut self, key: K, value: V) -> Option<(K, V)> {
+        if let Some(node) = self.find(&key) {
+            node.borrow_mut(&mut self.list.token).data.1 = value;
+            self.list.move_to_front(&node);
+            return None;
+        }
+        self.list.push_front((key, value));
+        if self.list.len() > self.capacity {
+            self.list.pop_back()
+        } else {
+            None
         }
     }
-}
 
-mod dllist_client_lib {
-    use ghost_cell::GhostToken;
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
 
-    use crate::{init_list, ListWrapper, Node};
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
 
-    pub fn list_wrapper_usage() {
-        // ListWrapper can store the token that owns its list nodes
-        // This allows not passing token everywhere
-        GhostToken::new(|mut token| {
-            let list = ListWrapper::create(token, [1, 2, 3, 4]);
-            println!("{:?}", list);
+    /// Entries from the most to the least recently used.
+    pub fn iter(&self) -> Iter<'id, '_, (K, V)> {
+        self.list.iter()
+    }
 
-            for n in list.iter() {
-                println!("{}", n);
+    fn find(&self, key: &K) -> Option<NodePtr<'id, (K, V)>> {
+        let mut cur = self.list.head.clone();
+        while let Some(node) = cur {
+            if node.borrow(&self.list.token).data.0 == *key {
+                return Some(node);
             }
-        });
+            cur = node.borrow(&self.list.token).next().cloned();
+        }
+        None
+    }
+}
+
+/// Lists are equal when they hold equal elements in the same order, even if their
+/// brands differ.
+impl<'id, 'id2, T: PartialEq> PartialEq<ListWrapper<'id2, T>> for ListWrapper<'id, T> {
+    fn eq(&self, other: &ListWrapper<'id2, T>) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
     }
+}
 
-    pub fn view_as_vec() {
-        GhostToken::new(|mut token| {
-            let (list, tail) = init_list(&mut token, 5);
+impl<'id, T: Eq> Eq for ListWrapper<'id, T> {}
 
-            let view = Node::view_as_vec(&list, &token);
-            println!("{:?}", view);
-        });
+/// Compares the elements lexicographically, like `Vec` does, across brands as well.
+impl<'id, 'id2, T: PartialOrd> PartialOrd<ListWrapper<'id2, T>> for ListWrapper<'id, T> {
+    fn partial_cmp(&self, other: &ListWrapper<'id2, T>) -> Option<Ordering> {
+        self.iter().partial_cmp(other.iter())
     }
+}
 
-    pub fn run_all_examples() {
-        list_wrapper_usage();
-        view_as_vec();
+impl<'id, T: Ord> Ord for ListWrapper<'id, T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other.iter())
     }
 }
 
-fn main() {
-    ownership::client_lib::run_all_examples();
-    dllist_client_lib::run_all_examples();
+/// Hashes the length and then the elements in order, like a slice does.
+impl<'id, T: Hash> Hash for ListWrapper<'id, T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.len.hash(state);
+        for data in self.iter() {
+            data.hash(state);
+        }
+    }
 }
@@ -0,0 +1,30 @@
+//! The doubly-linked lists behind the demos, one module per kind of cell.
+//!
+//! Each binary in `src/bin` shows off the list of the module with the same name.
+//!
+//! The ghost_cell list only needs `core` and `alloc`. Without the default `std` feature
+//! the crate is `no_std` and consists of just that module, plus the helpers the demos share.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod cell_family;
+#[cfg(feature = "std")]
+pub mod convert;
+#[doc(hidden)]
+pub mod demo_support;
+pub mod ghost_cell;
+#[cfg(feature = "std")]
+pub mod qcell;
+#[cfg(feature = "std")]
+pub mod tcell;
+
+/// The result of `Arc::try_unwrap` or `Rc::try_unwrap` on a node that a list has just
+/// unlinked. The lists that use this never hand out their nodes, so there can't be another
+/// reference to it.
+#[cfg(feature = "std")]
+fn unwrap_unlinked<C, P>(unwrapped: Result<C, P>) -> C {
+    unwrapped.unwrap_or_else(|_| panic!("unlinked node is still referenced from elsewhere"))
+}
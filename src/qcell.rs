@@ -1,114 +1,223 @@
-use client_lib::{dynamic_owner_check, simple_usage};
+use std::sync::{Arc, Weak};
 
-mod dllist {
-    use std::sync::{Arc, Weak};
+use qcell::{QCell, QCellOwner};
 
-    use qcell::{QCell, QCellOwner};
-
-    pub struct Node<T> {
-        pub data: T,
-        next: Option<NodePtr<T>>,
-        prev: Option<WeakNodePtr<T>>,
+pub struct Node<T> {
+    pub data: T,
+    next: Option<NodePtr<T>>,
+    prev: Option<WeakNodePtr<T>>,
+}
+pub type NodePtr<T> = Arc<QCell<Node<T>>>;
+pub type WeakNodePtr<T> = Weak<QCell<Node<T>>>;
+
+impl<T> Node<T> {
+    pub fn new(value: T, owner: &QCellOwner) -> NodePtr<T> {
+        Arc::new(QCell::new(
+            owner,
+            Self {
+                data: value,
+                prev: None,
+                next: None,
+            },
+        ))
     }
-    pub type NodePtr<T> = Arc<QCell<Node<T>>>;
-    pub type WeakNodePtr<T> = Weak<QCell<Node<T>>>;
-
-    impl<T> Node<T> {
-        pub fn new(value: T, owner: &QCellOwner) -> NodePtr<T> {
-            Arc::new(QCell::new(
-                owner,
-                Self {
-                    data: value,
-                    prev: None,
-                    next: None,
-                },
-            ))
+
+    /// Unlink the nodes adjacent to `node`. The node will have `next` and `prev` be `None` after this.
+    pub fn remove(node: &NodePtr<T>, token: &mut QCellOwner) {
+        let node = node.rw(token);
+
+        let old_prev: Option<NodePtr<T>> = node.prev.take().and_then(|p| p.upgrade());
+        let old_next: Option<NodePtr<T>> = node.next.take();
+        if let Some(old_next) = &old_next {
+            old_next.rw(token).prev = old_prev.as_ref().map(Arc::downgrade);
+        }
+        if let Some(old_prev) = &old_prev {
+            old_prev.rw(token).next = old_next;
         }
+    }
 
-        /// Unlink the nodes adjacent to `node`. The node will have `next` and `prev` be `None` after this.
-        pub fn remove(node: &NodePtr<T>, token: &mut QCellOwner) {
-            let node = node.rw(token);
+    /// Insert `node2` right after `node1` in the list.
+    /// Inserting a node after itself is a no-op.
+    pub fn insert_next(node1: &NodePtr<T>, node2: NodePtr<T>, token: &mut QCellOwner) {
+        if Arc::ptr_eq(node1, &node2) {
+            return;
+        }
+        Self::remove(&node2, token);
 
-            let old_prev: Option<NodePtr<T>> = node.prev.take().and_then(|p| p.upgrade());
-            let old_next: Option<NodePtr<T>> = node.next.take();
-            if let Some(old_next) = &old_next {
-                old_next.rw(token).prev = old_prev.as_ref().map(|p| Arc::downgrade(&p));
-            }
-            if let Some(old_prev) = &old_prev {
-                old_prev.rw(token).next = old_next;
-            }
+        let node1_old_next: Option<NodePtr<T>> = node1.rw(token).next.take();
+        if let Some(node1_old_next) = &node1_old_next {
+            node1_old_next.rw(token).prev = Some(Arc::downgrade(&node2));
         }
 
-        /// Insert `node2` right after `node1` in the list.
-        pub fn insert_next(node1: &NodePtr<T>, node2: NodePtr<T>, token: &mut QCellOwner) {
-            Self::remove(&node2, token);
+        let node2_inner: &mut Node<T> = node2.rw(token);
+        node2_inner.prev = Some(Arc::downgrade(node1));
+        node2_inner.next = node1_old_next;
 
-            let node1_old_next: Option<NodePtr<T>> = node1.rw(token).next.take();
-            if let Some(node1_old_next) = &node1_old_next {
-                node1_old_next.rw(token).prev = Some(Arc::downgrade(&node2));
-            }
+        node1.rw(token).next = Some(node2);
+    }
 
-            let node2_inner: &mut Node<T> = node2.rw(token);
-            node2_inner.prev = Some(Arc::downgrade(node1));
-            node2_inner.next = node1_old_next;
+    pub fn from_iter<I: IntoIterator<Item = T>>(
+        token: &mut QCellOwner,
+        elements: I,
+    ) -> Option<NodePtr<T>> {
+        let mut iter = elements.into_iter();
+        let first_element = iter.next()?;
+        let head = Node::new(first_element, token);
+        let mut tail = Arc::clone(&head);
+        for e in iter {
+            let node = Node::new(e, token);
+            Node::insert_next(&tail, Arc::clone(&node), token);
+            tail = node;
+        }
+        Option::Some(head)
+    }
 
-            node1.rw(token).next = Some(node2);
+    pub fn view_as_vec<'a>(head: Option<&'a NodePtr<T>>, token: &'a QCellOwner) -> Vec<&'a T> {
+        let mut cur: Option<&NodePtr<T>> = head;
+        let mut v: Vec<&'a T> = vec![];
+        while let Some(node) = cur {
+            v.push(&node.ro(token).data);
+            cur = node.ro(token).next.as_ref();
         }
+        v
+    }
 
-        pub fn from_iter<I: IntoIterator<Item = T>>(
-            token: &mut QCellOwner,
-            elements: I,
-        ) -> Option<NodePtr<T>> {
-            let mut iter = elements.into_iter();
-            let first_element = iter.next()?;
-            let head = Node::new(first_element, token);
-            let mut tail = Arc::clone(&head);
-            while let Some(e) = iter.next() {
-                let node = Node::new(e, token);
-                Node::insert_next(&tail, Arc::clone(&node), token);
-                tail = node;
-            }
-            Option::Some(head)
+    pub fn next(&self) -> Option<&NodePtr<T>> {
+        self.next.as_ref()
+    }
+
+    /// The last node of the list starting at `head`.
+    pub fn tail(head: &NodePtr<T>, token: &QCellOwner) -> NodePtr<T> {
+        let mut tail = head;
+        while let Some(next) = tail.ro(token).next() {
+            tail = next;
         }
+        Arc::clone(tail)
+    }
 
-        pub fn view_as_vec<'a>(head: Option<&'a NodePtr<T>>, token: &'a QCellOwner) -> Vec<&'a T> {
-            let mut cur: Option<&NodePtr<T>> = head;
-            let mut v: Vec<&'a T> = vec![];
-            while let Some(node) = cur {
-                v.push(&node.ro(token).data);
-                cur = (&node.ro(token).next).as_ref();
-            }
-            v
+    /// The number of nodes from `head` to the end, following the `next` links.
+    pub fn len(head: &NodePtr<T>, token: &QCellOwner) -> usize {
+        let mut len = 1;
+        let mut cur = head;
+        while let Some(next) = cur.ro(token).next() {
+            len += 1;
+            cur = next;
         }
+        len
     }
-}
 
-pub mod client_lib {
-    use qcell::QCellOwner;
+    /// The number of nodes from `tail` back to the head, following the `prev` links.
+    /// Should always agree with `len` when called on the two ends of the same list.
+    pub fn len_rev(tail: &NodePtr<T>, token: &QCellOwner) -> usize {
+        let mut len = 0;
+        Self::for_each_rev(tail, token, |_| len += 1);
+        len
+    }
 
-    use super::dllist::Node;
+    /// Take the list apart front to back and collect the data of its nodes.
+    ///
+    /// Every node is unlinked, but the data can only be moved out of a node nobody else
+    /// references: nodes that are still aliased elsewhere are skipped, and their data
+    /// stays reachable through the other aliases.
+    pub fn into_vec(head: Option<NodePtr<T>>, token: &mut QCellOwner) -> Vec<T> {
+        let mut v = vec![];
+        let mut cur = head;
+        while let Some(node) = cur {
+            cur = node.rw(token).next.take();
+            if let Some(next) = &cur {
+                next.rw(token).prev = None;
+            }
+            if let Ok(cell) = Arc::try_unwrap(node) {
+                v.push(cell.into_inner().data);
+            }
+        }
+        v
+    }
 
-    pub fn simple_usage() {
-        let mut token = QCellOwner::new();
-        let list1 = Node::from_iter(&mut token, [1, 2, 3]);
-        println!("{:?}", Node::view_as_vec(list1.as_ref(), &token));
+    /// Unlink every node whose data fails `pred` and return the new head,
+    /// which is `None` if nothing was kept.
+    pub fn retain(
+        head: Option<NodePtr<T>>,
+        token: &mut QCellOwner,
+        pred: impl Fn(&T) -> bool,
+    ) -> Option<NodePtr<T>> {
+        let mut new_head = None;
+        let mut cur = head;
+        while let Some(node) = cur {
+            cur = node.ro(token).next.clone();
+            if pred(&node.ro(token).data) {
+                new_head.get_or_insert(node);
+            } else {
+                Self::remove(&node, token);
+            }
+        }
+        new_head
     }
 
-    pub fn dynamic_owner_check() {
-        let mut token1 = QCellOwner::new();
-        let list1 = Node::from_iter(&mut token1, [1, 2, 3]);
-        let token2 = QCellOwner::new();
+    /// Sort the list ascending with a merge sort that relinks the nodes, returning the
+    /// new head. The sort is stable.
+    pub fn sort(head: Option<NodePtr<T>>, token: &mut QCellOwner) -> Option<NodePtr<T>>
+    where
+        T: Ord,
+    {
+        let head = head?;
+        let len = Self::len(&head, token);
+        if len == 1 {
+            return Some(head);
+        }
+        // cut the list after its first half
+        let mut last = Arc::clone(&head);
+        for _ in 1..len / 2 {
+            last = Arc::clone(last.ro(token).next().unwrap());
+        }
+        let second_half = last.rw(token).next.take().unwrap();
+        second_half.rw(token).prev = None;
 
-        // panics, dynamic check of owner fails:
-        // println!("{:?}", list1.map(|l| l.ro(&token2).data))
+        let a = Self::sort(Some(head), token);
+        let b = Self::sort(Some(second_half), token);
+        Self::merge(a, b, token)
     }
 
-    pub fn run_all_examples() {
-        simple_usage();
-        dynamic_owner_check();
+    /// Merge two ascending lists by relinking their nodes. On ties, elements of `a` go first.
+    fn merge(
+        mut a: Option<NodePtr<T>>,
+        mut b: Option<NodePtr<T>>,
+        token: &mut QCellOwner,
+    ) -> Option<NodePtr<T>>
+    where
+        T: Ord,
+    {
+        let mut head: Option<NodePtr<T>> = None;
+        let mut tail: Option<NodePtr<T>> = None;
+        loop {
+            let source = match (&a, &b) {
+                (Some(x), Some(y)) if x.ro(token).data <= y.ro(token).data => &mut a,
+                (Some(_), Some(_)) | (None, Some(_)) => &mut b,
+                (Some(_), None) => &mut a,
+                (None, None) => break,
+            };
+            // detach the smallest node from its list ...
+            let node = source.take().unwrap();
+            *source = node.rw(token).next.take();
+
+            // ... and append it to the merged one
+            node.rw(token).prev = tail.as_ref().map(Arc::downgrade);
+            match &tail {
+                Some(tail) => tail.rw(token).next = Some(Arc::clone(&node)),
+                None => head = Some(Arc::clone(&node)),
+            }
+            tail = Some(node);
+        }
+        head
     }
-}
 
-fn main() {
-    client_lib::run_all_examples();
+    /// Call `f` on every element from `tail` back to the head, following the `prev` links.
+    pub fn for_each_rev(tail: &NodePtr<T>, token: &QCellOwner, mut f: impl FnMut(&T)) {
+        let mut cur: Option<NodePtr<T>> = Some(Arc::clone(tail));
+        while let Some(node) = cur {
+            let inner = node.ro(token);
+            f(&inner.data);
+            cur = inner.prev.as_ref().and_then(Weak::upgrade);
+        }
+    }
 }
@@ -1,178 +1,425 @@
-mod dllist_lib {
-    use std::sync::{Arc, Weak};
+use std::sync::{Arc, Weak};
 
-    use qcell::{TCell, TCellOwner};
+use qcell::{TCell, TCellOwner};
 
-    pub struct Node<T, Brand> {
-        pub data: T,
-        next: Option<NodePtr<T, Brand>>,
-        prev: Option<WeakNodePtr<T, Brand>>,
+pub struct Node<T, Brand> {
+    pub data: T,
+    next: Option<NodePtr<T, Brand>>,
+    prev: Option<WeakNodePtr<T, Brand>>,
+}
+pub type NodePtr<T, Brand> = Arc<TCell<Brand, Node<T, Brand>>>;
+pub type WeakNodePtr<T, Brand> = Weak<TCell<Brand, Node<T, Brand>>>;
+
+impl<T, Brand> Node<T, Brand> {
+    pub fn new(value: T) -> NodePtr<T, Brand> {
+        Arc::new(TCell::new(Self {
+            data: value,
+            prev: None,
+            next: None,
+        }))
     }
-    pub type NodePtr<T, Brand> = Arc<TCell<Brand, Node<T, Brand>>>;
-    pub type WeakNodePtr<T, Brand> = Weak<TCell<Brand, Node<T, Brand>>>;
 
-    impl<T, Brand> Node<T, Brand> {
-        pub fn new(value: T) -> NodePtr<T, Brand> {
-            Arc::new(TCell::new(Self {
-                data: value,
-                prev: None,
-                next: None,
-            }))
+    /// Unlink the nodes adjacent to `node`. The node will have `next` and `prev` be `None` after this.
+    pub fn remove(node: &NodePtr<T, Brand>, token: &mut TCellOwner<Brand>) {
+        let node = node.rw(token);
+
+        let old_prev: Option<NodePtr<T, Brand>> = node.prev.take().and_then(|p| p.upgrade());
+        let old_next: Option<NodePtr<T, Brand>> = node.next.take();
+        if let Some(old_next) = &old_next {
+            old_next.rw(token).prev = old_prev.as_ref().map(Arc::downgrade);
         }
+        if let Some(old_prev) = &old_prev {
+            old_prev.rw(token).next = old_next;
+        }
+    }
 
-        /// Unlink the nodes adjacent to `node`. The node will have `next` and `prev` be `None` after this.
-        pub fn remove(node: &NodePtr<T, Brand>, token: &mut TCellOwner<Brand>) {
-            let node = node.rw(token);
+    /// Unlink `node` like `remove` and return its data.
+    ///
+    /// `token` is only needed to relink the neighbours. The data is then moved out with
+    /// `TCell::into_inner`, which takes the cell by value instead of checking the owner, so
+    /// `node` has to be the last `Arc` to it. If another alias keeps it alive, the node is
+    /// unlinked all the same but `None` is returned.
+    pub fn remove_value(node: NodePtr<T, Brand>, token: &mut TCellOwner<Brand>) -> Option<T> {
+        Self::remove(&node, token);
+        Arc::try_unwrap(node)
+            .ok()
+            .map(|cell| cell.into_inner().data)
+    }
 
-            let old_prev: Option<NodePtr<T, Brand>> = node.prev.take().and_then(|p| p.upgrade());
-            let old_next: Option<NodePtr<T, Brand>> = node.next.take();
-            if let Some(old_next) = &old_next {
-                old_next.rw(token).prev = old_prev.as_ref().map(|p| Arc::downgrade(&p));
-            }
-            if let Some(old_prev) = &old_prev {
-                old_prev.rw(token).next = old_next;
+    /// Remove the element stored in `node` in O(1) relinking, like `Vec::swap_remove`:
+    /// the tail's data is moved into `node` and the tail is unlinked instead.
+    /// The order of the remaining elements is not preserved.
+    ///
+    /// Returns `None` and leaves the list untouched if the tail is aliased outside the
+    /// list (or, when `node` is the tail, aliased anywhere else), since the data can't be
+    /// moved out of it then.
+    pub fn swap_remove(
+        head: &NodePtr<T, Brand>,
+        node: NodePtr<T, Brand>,
+        token: &mut TCellOwner<Brand>,
+    ) -> Option<T> {
+        let tail = Node::tail(head, token);
+        if Arc::ptr_eq(&node, &tail) {
+            drop(tail);
+            if Arc::strong_count(&node) > 2 {
+                return None;
             }
+            return Self::remove_value(node, token);
+        }
+        // One reference from the predecessor's `next`, one from `tail` itself.
+        if Arc::strong_count(&tail) > 2 {
+            return None;
+        }
+        let (node, tail_inner) = token.rw2(&node, &tail);
+        std::mem::swap(&mut node.data, &mut tail_inner.data);
+        Self::remove_value(tail, token)
+    }
+
+    /// Insert `node2` right after `node1` in the list.
+    /// Inserting a node after itself is a no-op.
+    pub fn insert_next(
+        node1: &NodePtr<T, Brand>,
+        node2: NodePtr<T, Brand>,
+        token: &mut TCellOwner<Brand>,
+    ) {
+        if Arc::ptr_eq(node1, &node2) {
+            return;
         }
+        Self::remove(&node2, token);
 
-        /// Insert `node2` right after `node1` in the list.
-        pub fn insert_next(
-            node1: &NodePtr<T, Brand>,
-            node2: NodePtr<T, Brand>,
-            token: &mut TCellOwner<Brand>,
-        ) {
-            Self::remove(&node2, token);
+        let node1_old_next: Option<NodePtr<T, Brand>> = node1.rw(token).next.take();
+        if let Some(node1_old_next) = &node1_old_next {
+            node1_old_next.rw(token).prev = Some(Arc::downgrade(&node2));
+        }
 
-            let node1_old_next: Option<NodePtr<T, Brand>> = node1.rw(token).next.take();
-            if let Some(node1_old_next) = &node1_old_next {
-                node1_old_next.rw(token).prev = Some(Arc::downgrade(&node2));
-            }
+        let node2_inner: &mut Node<T, Brand> = node2.rw(token);
+        node2_inner.prev = Some(Arc::downgrade(node1));
+        node2_inner.next = node1_old_next;
 
-            let node2_inner: &mut Node<T, Brand> = node2.rw(token);
-            node2_inner.prev = Some(Arc::downgrade(node1));
-            node2_inner.next = node1_old_next;
-
-            node1.rw(token).next = Some(node2);
-        }
-
-        pub fn from_iter<I: IntoIterator<Item = T>>(
-            token: &mut TCellOwner<Brand>,
-            elements: I,
-        ) -> Option<NodePtr<T, Brand>> {
-            let mut iter = elements.into_iter();
-            let first_element = iter.next()?;
-            let head = Node::new(first_element);
-            let mut tail = Arc::clone(&head);
-            while let Some(e) = iter.next() {
-                let node = Node::new(e);
-                Node::insert_next(&tail, Arc::clone(&node), token);
-                tail = node;
-            }
-            Option::Some(head)
-        }
-
-        pub fn view_as_vec<'a>(
-            head: Option<&'a NodePtr<T, Brand>>,
-            token: &'a TCellOwner<Brand>,
-        ) -> Vec<&'a T> {
-            let mut cur: Option<&NodePtr<T, Brand>> = head;
-            let mut v: Vec<&'a T> = vec![];
-            while let Some(node) = cur {
-                v.push(&node.ro(token).data);
-                cur = (&node.ro(token).next).as_ref();
+        node1.rw(token).next = Some(node2);
+    }
+
+    pub fn from_iter<I: IntoIterator<Item = T>>(
+        token: &mut TCellOwner<Brand>,
+        elements: I,
+    ) -> Option<NodePtr<T, Brand>> {
+        let mut iter = elements.into_iter();
+        let first_element = iter.next()?;
+        let head = Node::new(first_element);
+        let mut tail = Arc::clone(&head);
+        for e in iter {
+            let node = Node::new(e);
+            Node::insert_next(&tail, Arc::clone(&node), token);
+            tail = node;
+        }
+        Option::Some(head)
+    }
+
+    pub fn view_as_vec<'a>(
+        head: Option<&'a NodePtr<T, Brand>>,
+        token: &'a TCellOwner<Brand>,
+    ) -> Vec<&'a T> {
+        let mut cur: Option<&NodePtr<T, Brand>> = head;
+        let mut v: Vec<&'a T> = vec![];
+        while let Some(node) = cur {
+            v.push(&node.ro(token).data);
+            cur = node.ro(token).next.as_ref();
+        }
+        v
+    }
+
+    /// Like `view_as_vec`, but walks the `prev` links from `tail` back to the head.
+    /// Those links are weak, so nothing guarantees a node stays alive for as long as
+    /// the token is borrowed; the nodes themselves are returned instead of references
+    /// to their data.
+    pub fn rev_view_as_vec(
+        tail: &NodePtr<T, Brand>,
+        token: &TCellOwner<Brand>,
+    ) -> Vec<NodePtr<T, Brand>> {
+        let mut cur: Option<NodePtr<T, Brand>> = Some(Arc::clone(tail));
+        let mut v: Vec<NodePtr<T, Brand>> = vec![];
+        while let Some(node) = cur {
+            cur = node.ro(token).prev.as_ref().and_then(Weak::upgrade);
+            v.push(node);
+        }
+        v
+    }
+
+    pub fn next(&self) -> Option<&NodePtr<T, Brand>> {
+        self.next.as_ref()
+    }
+
+    /// The last node of the list starting at `head`.
+    pub fn tail(head: &NodePtr<T, Brand>, token: &TCellOwner<Brand>) -> NodePtr<T, Brand> {
+        let mut tail = head;
+        while let Some(next) = tail.ro(token).next() {
+            tail = next;
+        }
+        Arc::clone(tail)
+    }
+
+    /// Append `elements` at the end of the list that `tail` belongs to (walking to its
+    /// true tail first) and return the new tail.
+    pub fn extend(
+        tail: &NodePtr<T, Brand>,
+        elements: impl IntoIterator<Item = T>,
+        token: &mut TCellOwner<Brand>,
+    ) -> NodePtr<T, Brand> {
+        let mut tail = Node::tail(tail, token);
+        for e in elements {
+            let node = Node::new(e);
+            Node::insert_next(&tail, Arc::clone(&node), token);
+            tail = node;
+        }
+        tail
+    }
+
+    /// Build a new list of `f` applied to every element. Both lists are under the same
+    /// brand, so reading the source and linking the copy take turns on the same token.
+    pub fn map<U>(
+        head: Option<&NodePtr<T, Brand>>,
+        token: &mut TCellOwner<Brand>,
+        f: impl Fn(&T) -> U,
+    ) -> Option<NodePtr<U, Brand>> {
+        let mut cur = Arc::clone(head?);
+        let new_head = Node::new(f(&cur.ro(token).data));
+        let mut new_tail = Arc::clone(&new_head);
+        while let Some(next) = cur.ro(token).next().cloned() {
+            let node = Node::new(f(&next.ro(token).data));
+            Node::insert_next(&new_tail, Arc::clone(&node), token);
+            new_tail = node;
+            cur = next;
+        }
+        Some(new_head)
+    }
+
+    /// Drop everything after the first `len` nodes. A no-op if the list isn't longer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `len` is 0: the head can't be dropped from under the caller.
+    pub fn truncate(head: &NodePtr<T, Brand>, len: usize, token: &mut TCellOwner<Brand>) {
+        assert!(len > 0, "can't truncate a list to zero elements");
+        let mut last = Arc::clone(head);
+        for _ in 1..len {
+            match last.ro(token).next() {
+                Some(next) => last = Arc::clone(next),
+                None => return,
             }
-            v
         }
+        if let Some(rest) = last.rw(token).next.take() {
+            rest.rw(token).prev = None;
+        }
+    }
+
+    fn into_data(node: NodePtr<T, Brand>) -> T {
+        crate::unwrap_unlinked(Arc::try_unwrap(node))
+            .into_inner()
+            .data
+    }
+
+    /// Append `node` to the list between `head` and `tail`, as `TDeque` and `TRingBuffer`
+    /// store it.
+    fn link_last(
+        head: &mut Option<NodePtr<T, Brand>>,
+        tail: &mut Option<NodePtr<T, Brand>>,
+        node: NodePtr<T, Brand>,
+        token: &mut TCellOwner<Brand>,
+    ) {
+        match tail.take() {
+            None => *head = Some(Arc::clone(&node)),
+            Some(old_tail) => Node::insert_next(&old_tail, Arc::clone(&node), token),
+        }
+        *tail = Some(node);
+    }
+
+    fn iter_data<'a>(
+        head: Option<&'a NodePtr<T, Brand>>,
+        token: &'a TCellOwner<Brand>,
+    ) -> impl Iterator<Item = &'a T> {
+        std::iter::successors(head, move |node| node.ro(token).next.as_ref())
+            .map(move |node| &node.ro(token).data)
+    }
 
-        pub fn next(&self) -> Option<&NodePtr<T, Brand>> {
-            self.next.as_ref()
+    /// Free the list starting at `head` one node at a time. Dropping `head` as it is would
+    /// free the nodes recursively, which overflows the stack for a long list.
+    fn free_from(head: Option<NodePtr<T, Brand>>, token: &mut TCellOwner<Brand>) {
+        let mut next = head;
+        while let Some(node) = next {
+            next = node.rw(token).next.take();
         }
     }
 }
 
-mod client_lib {
-    use std::sync::Arc;
+impl<T: Clone, Brand> Node<T, Brand> {
+    /// Deep-copy the list into fresh nodes under the same brand.
+    pub fn clone_list(
+        head: Option<&NodePtr<T, Brand>>,
+        token: &mut TCellOwner<Brand>,
+    ) -> Option<NodePtr<T, Brand>> {
+        Node::map(head, token, T::clone)
+    }
+}
 
-    use qcell::TCellOwner;
+/// A deque that stores the owner of its nodes together with the nodes,
+/// so the token doesn't have to be passed around by the user.
+pub struct TDeque<T, Brand: 'static> {
+    owner: TCellOwner<Brand>,
+    head: Option<NodePtr<T, Brand>>,
+    tail: Option<NodePtr<T, Brand>>,
+    len: usize,
+}
 
-    use crate::dllist_lib::{Node, NodePtr};
+impl<T, Brand> TDeque<T, Brand> {
+    pub fn new(owner: TCellOwner<Brand>) -> Self {
+        Self {
+            owner,
+            head: None,
+            tail: None,
+            len: 0,
+        }
+    }
 
-    pub fn simple_usage() {
-        struct Brand;
-        let mut token = TCellOwner::<Brand>::new();
-        let list1 = Node::from_iter(&mut token, [1, 2, 3]);
-        println!("{:?}", Node::view_as_vec(list1.as_ref(), &token));
+    pub fn len(&self) -> usize {
+        self.len
     }
 
-    pub fn unique_owner_restriction() {
-        struct Brand;
-        let mut token1 = TCellOwner::<Brand>::new();
-        let list1 = Node::from_iter(&mut token1, [1, 2, 3]);
-        // will panic:
-        // let token2 = TCellOwner::<Brand>::new();
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn push_front(&mut self, value: T) {
+        let node = Node::new(value);
+        match self.head.take() {
+            None => self.tail = Some(Arc::clone(&node)),
+            Some(old_head) => {
+                old_head.rw(&mut self.owner).prev = Some(Arc::downgrade(&node));
+                node.rw(&mut self.owner).next = Some(old_head);
+            }
+        }
+        self.head = Some(node);
+        self.len += 1;
     }
 
-    pub fn static_owner_check() {
-        struct Brand;
-        let mut token1 = TCellOwner::<Brand>::new();
-        let list1 = Node::from_iter(&mut token1, [1, 2, 3]);
+    pub fn push_back(&mut self, value: T) {
+        let node = Node::new(value);
+        Node::link_last(&mut self.head, &mut self.tail, node, &mut self.owner);
+        self.len += 1;
+    }
 
-        // does not compile
-        // struct Brand2;
-        // let token2 = TCellOwner::<Brand2>::new();
-        // println!("{:?}", list1.map(|l| l.ro(&token2).data))
+    pub fn pop_front(&mut self) -> Option<T> {
+        let old_head = self.head.take()?;
+        match old_head.rw(&mut self.owner).next.take() {
+            None => self.tail = None,
+            Some(new_head) => {
+                new_head.rw(&mut self.owner).prev = None;
+                self.head = Some(new_head);
+            }
+        }
+        self.len -= 1;
+        Some(Node::into_data(old_head))
     }
 
-    pub fn two_simultaneous_borrows() {
-        struct Brand;
-        let mut token = TCellOwner::<Brand>::new();
-        let first = Node::from_iter(&mut token, [1, 2]).unwrap();
-        let second = Arc::clone(first.ro(&token).next().unwrap());
-        let (first_ref, second_ref) = token.rw2(&first, &second);
+    pub fn pop_back(&mut self) -> Option<T> {
+        let old_tail = self.tail.take()?;
+        let new_tail = old_tail
+            .rw(&mut self.owner)
+            .prev
+            .take()
+            .and_then(|p| p.upgrade());
+        match new_tail {
+            None => self.head = None,
+            Some(new_tail) => {
+                new_tail.rw(&mut self.owner).next = None;
+                self.tail = Some(new_tail);
+            }
+        }
+        self.len -= 1;
+        Some(Node::into_data(old_tail))
+    }
 
-        first_ref.data = 61;
-        second_ref.data = 62;
-        println!("{:?}", Node::view_as_vec(Option::Some(&first), &token));
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        Node::iter_data(self.head.as_ref(), &self.owner)
     }
+}
 
-    pub fn two_simultaneous_borrows_panic() {
-        // 2 simultaneous borrows panic as references point to one cell
-        struct Brand;
-        let mut token = TCellOwner::<Brand>::new();
-        let first = Node::from_iter(&mut token, [1]).unwrap();
-        let second = Arc::clone(&first);
-        // panics:
-        // let (first_ref, second_ref) = token.rw2(&first, &second);
+impl<T, Brand> Drop for TDeque<T, Brand> {
+    fn drop(&mut self) {
+        Node::free_from(self.head.take(), &mut self.owner);
     }
+}
 
-    pub fn two_structs_in_one_vector_fail() {
-        trait Brand {
+/// A buffer of the `capacity` most recently pushed elements. Once it is full, each
+/// `push` reuses the node of the oldest element for the new one.
+pub struct TRingBuffer<T, Brand: 'static> {
+    owner: TCellOwner<Brand>,
+    head: Option<NodePtr<T, Brand>>,
+    tail: Option<NodePtr<T, Brand>>,
+    len: usize,
+    capacity: usize,
+}
 
+impl<T, Brand> TRingBuffer<T, Brand> {
+    /// # Panics
+    ///
+    /// Panics if `capacity` is 0.
+    pub fn new(owner: TCellOwner<Brand>, capacity: usize) -> Self {
+        assert!(
+            capacity > 0,
+            "a ring buffer needs room for at least one element"
+        );
+        Self {
+            owner,
+            head: None,
+            tail: None,
+            len: 0,
+            capacity,
         }
+    }
 
-        struct Brand1;
-        let mut token1 = TCellOwner::<Brand1>::new();
-        let first = Node::from_iter(&mut token1, [1, 2, 3]);
+    pub fn len(&self) -> usize {
+        self.len
+    }
 
-        struct Brand2;
-        let mut token2 = TCellOwner::<Brand2>::new();
-        let second = Node::from_iter(&mut token2, [1, 2, 3]);
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
 
-        // does not compile:
-        // struct MultipleListsContainer<T> {
-        //     lists: Vec<Option<NodePtr<T, dyn Brand>>>,
-        // }
+    pub fn capacity(&self) -> usize {
+        self.capacity
     }
 
-    pub fn run_all_examples() {
-        simple_usage();
-        unique_owner_restriction();
-        static_owner_check();
-        two_simultaneous_borrows();
-        two_simultaneous_borrows_panic();
-        two_structs_in_one_vector_fail();
+    /// Append `value` as the newest element. If the buffer is full, the oldest element
+    /// is overwritten and returned.
+    pub fn push(&mut self, value: T) -> Option<T> {
+        if self.len < self.capacity {
+            let node = Node::new(value);
+            Node::link_last(&mut self.head, &mut self.tail, node, &mut self.owner);
+            self.len += 1;
+            return None;
+        }
+
+        let oldest = self.head.take().unwrap();
+        if self.capacity > 1 {
+            let tail = self.tail.take().unwrap();
+            self.head = oldest.ro(&self.owner).next().cloned();
+            Node::insert_next(&tail, Arc::clone(&oldest), &mut self.owner);
+        } else {
+            self.head = Some(Arc::clone(&oldest));
+        }
+        let evicted = std::mem::replace(&mut oldest.rw(&mut self.owner).data, value);
+        self.tail = Some(oldest);
+        Some(evicted)
+    }
+
+    /// The elements from the oldest to the newest.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        Node::iter_data(self.head.as_ref(), &self.owner)
     }
 }
 
-fn main() {
-    client_lib::run_all_examples();
+impl<T, Brand> Drop for TRingBuffer<T, Brand> {
+    fn drop(&mut self) {
+        Node::free_from(self.head.take(), &mut self.owner);
+    }
 }